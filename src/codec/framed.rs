@@ -0,0 +1,168 @@
+use std::io::prelude::*;
+use std::io::{IoSlice, Result, Error, ErrorKind};
+
+use io::{NbBufReader, WriteExt, Status};
+use io::ring::RingBuf;
+use super::{Decoder, Encoder};
+
+const DEFAULT_MAX_FRAME_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_WRITE_CAPACITY: usize = 8 * 1024;
+
+/// A nonblocking stream carrying frames of a codec.
+///
+/// Incoming bytes are buffered until the codec can decode a whole frame from them, so frames may
+/// arrive split across any number of readiness events. Outgoing frames are queued and written out
+/// with `flush_nb`.
+///
+/// Frames longer than the maximum frame size, including any framing bytes, are refused in both
+/// directions, bounding the memory a peer can make the stream buffer.
+#[derive(Debug)]
+pub struct Framed<S, C> {
+    reader: NbBufReader<S>,
+    codec: C,
+    wbuf: RingBuf,
+    // Each frame is encoded here before being queued on `wbuf`.
+    frame: Vec<u8>,
+    max_frame_size: usize,
+    eof: bool,
+}
+
+impl<S: Read + Write, C> Framed<S, C> {
+    pub fn new(stream: S, codec: C) -> Framed<S, C> {
+        Framed {
+            reader: NbBufReader::new(stream),
+            codec: codec,
+            wbuf: RingBuf::with_capacity(DEFAULT_WRITE_CAPACITY),
+            frame: Vec::new(),
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            eof: false,
+        }
+    }
+
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    pub fn set_max_frame_size(&mut self, size: usize) {
+        self.max_frame_size = size;
+    }
+
+    /// Returns `true` once the stream has reached end-of-file and every buffered frame has been
+    /// read.
+    pub fn is_eof(&self) -> bool {
+        self.eof && self.reader.is_empty()
+    }
+
+    /// Writes queued frames to the stream until the queue is empty or the stream would block,
+    /// returning the number of bytes written and why writing stopped.
+    pub fn flush_nb(&mut self) -> Result<(usize, Status)> {
+        let res = {
+            let (a, b) = self.wbuf.as_slices();
+            self.reader.get_mut().write_nb_vectored(&[IoSlice::new(a), IoSlice::new(b)])
+        };
+
+        if let Ok((n, _)) = res {
+            self.wbuf.consume(n);
+        }
+        res
+    }
+
+    /// Returns `true` while queued bytes remain, and so the stream's writable readiness is still
+    /// of interest.
+    pub fn wants_writable(&self) -> bool {
+        !self.wbuf.is_empty()
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.reader.get_ref()
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        self.reader.get_mut()
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Unwraps the stream. Any buffered or queued bytes are lost.
+    pub fn into_inner(self) -> S {
+        self.reader.into_inner()
+    }
+}
+
+impl<S: Read + Write, C: Decoder> Framed<S, C> {
+    /// Reads the next frame.
+    ///
+    /// Returns `Ok(None)` once no whole frame is buffered and the stream would block, or has
+    /// reached end-of-file, as `is_eof` tells. Since it reads until the stream would block before
+    /// returning `None`, it suits edge-triggered registration.
+    ///
+    /// Returns an error of kind `InvalidData` if a frame exceeds the maximum frame size, and of
+    /// kind `UnexpectedEof` if the stream ends partway through a frame.
+    pub fn read_frame(&mut self) -> Result<Option<C::Item>> {
+        loop {
+            if let Some(frame) = try!(self.decode()) {
+                return Ok(Some(frame));
+            }
+
+            if self.eof {
+                return if self.reader.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(Error::new(ErrorKind::UnexpectedEof, "stream ended within a frame"))
+                };
+            }
+
+            match try!(self.reader.fill()) {
+                (_, Status::Drained) => return self.decode(),
+                (_, Status::BufferFull) => {}
+                (_, Status::Eof) => self.eof = true,
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Result<Option<C::Item>> {
+        let res = try!(self.codec.decode(self.reader.buffer()));
+
+        match res {
+            Some((frame, n)) => {
+                if n > self.max_frame_size {
+                    return Err(too_large(ErrorKind::InvalidData));
+                }
+                self.reader.consume(n);
+                Ok(Some(frame))
+            }
+            None if self.reader.len() > self.max_frame_size => {
+                Err(too_large(ErrorKind::InvalidData))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<S: Read + Write, C: Encoder> Framed<S, C> {
+    /// Queues a frame to be written by `flush_nb`.
+    ///
+    /// Returns an error of kind `InvalidInput` if the encoded frame exceeds the maximum frame
+    /// size, in which case nothing is queued.
+    pub fn write_frame(&mut self, frame: &C::Item) -> Result<()> {
+        self.frame.clear();
+
+        try!(self.codec.encode(frame, &mut self.frame));
+        if self.frame.len() > self.max_frame_size {
+            return Err(too_large(ErrorKind::InvalidInput));
+        }
+
+        self.wbuf.extend(&self.frame);
+        Ok(())
+    }
+}
+
+fn too_large(kind: ErrorKind) -> Error {
+    Error::new(kind, "frame exceeds the maximum frame size")
+}
@@ -0,0 +1,83 @@
+use std::io::{Result, Error, ErrorKind};
+
+use super::{Decoder, Encoder};
+
+/// Frames messages prefixed by their length, as an unsigned 16- or 32-bit integer in big- or
+/// little-endian byte order.
+///
+/// The length counts the message alone, not the prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthCodec {
+    width: usize,
+    big_endian: bool,
+}
+
+impl LengthCodec {
+    /// A 16-bit big-endian length prefix.
+    pub fn u16_be() -> LengthCodec {
+        LengthCodec { width: 2, big_endian: true }
+    }
+
+    /// A 16-bit little-endian length prefix.
+    pub fn u16_le() -> LengthCodec {
+        LengthCodec { width: 2, big_endian: false }
+    }
+
+    /// A 32-bit big-endian length prefix.
+    pub fn u32_be() -> LengthCodec {
+        LengthCodec { width: 4, big_endian: true }
+    }
+
+    /// A 32-bit little-endian length prefix.
+    pub fn u32_le() -> LengthCodec {
+        LengthCodec { width: 4, big_endian: false }
+    }
+
+    // The longest message the prefix can describe.
+    fn max_len(&self) -> u64 {
+        (1 << (8 * self.width)) - 1
+    }
+}
+
+impl Decoder for LengthCodec {
+    type Item = Vec<u8>;
+
+    fn decode(&mut self, buf: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+        if buf.len() < self.width {
+            return Ok(None);
+        }
+
+        let prefix = &buf[..self.width];
+        let len = if self.big_endian {
+            prefix.iter().fold(0u64, |len, &b| len << 8 | b as u64)
+        } else {
+            prefix.iter().rev().fold(0u64, |len, &b| len << 8 | b as u64)
+        } as usize;
+
+        if buf.len() - self.width < len {
+            Ok(None)
+        } else {
+            let end = self.width + len;
+            Ok(Some((buf[self.width..end].to_vec(), end)))
+        }
+    }
+}
+
+impl Encoder for LengthCodec {
+    type Item = [u8];
+
+    fn encode(&mut self, msg: &[u8], buf: &mut Vec<u8>) -> Result<()> {
+        if msg.len() as u64 > self.max_len() {
+            return Err(Error::new(ErrorKind::InvalidInput, "message too long for length prefix"));
+        }
+
+        let len = msg.len() as u64;
+        for i in 0..self.width {
+            let shift = if self.big_endian { self.width - 1 - i } else { i };
+            buf.push((len >> (8 * shift)) as u8);
+        }
+
+        buf.extend_from_slice(msg);
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use std::io::{Result, Error, ErrorKind};
+
+use super::{Decoder, Encoder};
+
+/// Frames lines terminated by `\n`.
+///
+/// Decoded lines exclude the terminator and any `\r` before it. Encoded lines are terminated by
+/// `\n` alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineCodec;
+
+impl LineCodec {
+    pub fn new() -> LineCodec {
+        LineCodec
+    }
+}
+
+impl Decoder for LineCodec {
+    type Item = Vec<u8>;
+
+    fn decode(&mut self, buf: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+        let end = match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => end,
+            None => return Ok(None),
+        };
+
+        let line = if end > 0 && buf[end - 1] == b'\r' {
+            &buf[..end - 1]
+        } else {
+            &buf[..end]
+        };
+        Ok(Some((line.to_vec(), end + 1)))
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = [u8];
+
+    fn encode(&mut self, line: &[u8], buf: &mut Vec<u8>) -> Result<()> {
+        if line.contains(&b'\n') {
+            return Err(Error::new(ErrorKind::InvalidInput, "line contains a newline"));
+        }
+
+        buf.extend_from_slice(line);
+        buf.push(b'\n');
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+//! Framing of byte streams into messages.
+//!
+//! A `Decoder` splits buffered bytes into frames and an `Encoder` writes frames out as bytes.
+//! `Framed` joins a codec to a nonblocking stream, reassembling frames from whatever bytes each
+//! readiness event delivers.
+
+use std::io::Result;
+
+mod framed;
+pub use self::framed::Framed;
+mod line;
+pub use self::line::LineCodec;
+mod length;
+pub use self::length::LengthCodec;
+mod netstring;
+pub use self::netstring::NetstringCodec;
+
+pub trait Decoder {
+    type Item;
+
+    /// Decodes a frame from the start of `buf`, returning it and the number of bytes it occupied,
+    /// or `None` if `buf` does not yet hold a whole frame.
+    ///
+    /// Returns an error of kind `InvalidData` if `buf` does not start with a valid frame.
+    fn decode(&mut self, buf: &[u8]) -> Result<Option<(Self::Item, usize)>>;
+}
+
+pub trait Encoder {
+    type Item: ?Sized;
+
+    /// Appends the encoding of `item` to `buf`.
+    ///
+    /// Returns an error of kind `InvalidInput` if `item` cannot be framed by this codec.
+    fn encode(&mut self, item: &Self::Item, buf: &mut Vec<u8>) -> Result<()>;
+}
@@ -0,0 +1,74 @@
+use std::io::{Result, Error, ErrorKind};
+
+use super::{Decoder, Encoder};
+
+// Enough digits for any length that fits in a `usize`.
+const MAX_DIGITS: usize = 20;
+
+/// Frames netstrings, messages of the form `<length>:<message>,` with the length in decimal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetstringCodec;
+
+impl NetstringCodec {
+    pub fn new() -> NetstringCodec {
+        NetstringCodec
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+impl Decoder for NetstringCodec {
+    type Item = Vec<u8>;
+
+    fn decode(&mut self, buf: &[u8]) -> Result<Option<(Vec<u8>, usize)>> {
+        let colon = match buf.iter().take(MAX_DIGITS + 1).position(|&b| b == b':') {
+            Some(colon) => colon,
+            None if buf.len() > MAX_DIGITS => return Err(invalid("netstring length too long")),
+            None => {
+                if buf.iter().all(|b| b.is_ascii_digit()) {
+                    return Ok(None);
+                }
+                return Err(invalid("netstring length is not a number"));
+            }
+        };
+
+        let digits = &buf[..colon];
+        if digits.is_empty() || !digits.iter().all(|b| b.is_ascii_digit()) {
+            return Err(invalid("netstring length is not a number"));
+        }
+        if digits.len() > 1 && digits[0] == b'0' {
+            return Err(invalid("netstring length has leading zeros"));
+        }
+
+        let mut len: usize = 0;
+        for &b in digits {
+            len = try!(len.checked_mul(10)
+                .and_then(|len| len.checked_add((b - b'0') as usize))
+                .ok_or_else(|| invalid("netstring length too long")));
+        }
+
+        let start = colon + 1;
+        if buf.len() - start <= len {
+            return Ok(None);
+        }
+        if buf[start + len] != b',' {
+            return Err(invalid("netstring not terminated by a comma"));
+        }
+
+        Ok(Some((buf[start..start + len].to_vec(), start + len + 1)))
+    }
+}
+
+impl Encoder for NetstringCodec {
+    type Item = [u8];
+
+    fn encode(&mut self, msg: &[u8], buf: &mut Vec<u8>) -> Result<()> {
+        buf.extend_from_slice(msg.len().to_string().as_bytes());
+        buf.push(b':');
+        buf.extend_from_slice(msg);
+        buf.push(b',');
+        Ok(())
+    }
+}
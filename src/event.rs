@@ -5,6 +5,7 @@ bitflags! {
         const WRITABLE = 0b0010,
         const ERROR = 0b0100,
         const HUP = 0b1000,
+        const PRIORITY = 0b1_0000,
     }
 }
 
@@ -40,4 +41,65 @@ impl EventSet {
     pub fn is_hup(&self) -> bool {
         self.contains(HUP)
     }
+
+    /// Urgent or out-of-band data is available to read.
+    pub fn priority() -> EventSet {
+        PRIORITY
+    }
+
+    pub fn is_priority(&self) -> bool {
+        self.contains(PRIORITY)
+    }
+}
+
+bitflags! {
+    /// Options controlling how readiness is reported for a registered file descriptor.
+    pub flags PollOpt: usize {
+        const EDGE = 0b0001,
+        const LEVEL = 0b0010,
+        const ONESHOT = 0b0100,
+        const EXCLUSIVE = 0b1000,
+    }
+}
+
+impl PollOpt {
+    /// Readiness is reported only when the file descriptor transitions into a ready state.
+    pub fn edge() -> PollOpt {
+        EDGE
+    }
+
+    pub fn is_edge(&self) -> bool {
+        self.contains(EDGE)
+    }
+
+    /// Readiness is reported for as long as the file descriptor remains ready.
+    pub fn level() -> PollOpt {
+        LEVEL
+    }
+
+    pub fn is_level(&self) -> bool {
+        self.contains(LEVEL)
+    }
+
+    /// Readiness is reported at most once, after which the file descriptor must be re-registered.
+    pub fn oneshot() -> PollOpt {
+        ONESHOT
+    }
+
+    pub fn is_oneshot(&self) -> bool {
+        self.contains(ONESHOT)
+    }
+
+    /// When the file descriptor is registered with several `Selector`s, readiness wakes only one
+    /// of those blocked polling it, rather than all of them.
+    ///
+    /// Only the epoll backend honours this, using `EPOLLEXCLUSIVE`, and it must be given at
+    /// registration, not in a re-registration. Other backends ignore it.
+    pub fn exclusive() -> PollOpt {
+        EXCLUSIVE
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.contains(EXCLUSIVE)
+    }
 }
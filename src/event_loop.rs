@@ -0,0 +1,259 @@
+use std::fmt;
+use std::mem;
+use std::io::{Result, Error, ErrorKind};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use event::{EventSet, PollOpt};
+use evented::Evented;
+use selector::{Selector, Fired};
+use token::Token;
+use waker::Waker;
+use wheel::Timeout;
+
+/// Callbacks invoked by an `EventLoop`.
+///
+/// Each callback receives the `EventLoop` itself, so file descriptors and timeouts can be
+/// registered, modified and removed, and the loop shut down, from within any of them.
+pub trait Handler: Sized {
+    /// The type of message sent to the loop through a `Sender`.
+    type Message: Send;
+
+    /// Called when a registered file descriptor is ready.
+    fn ready(&mut self, event_loop: &mut EventLoop<Self>, token: Token, evset: EventSet) {
+        let _ = (event_loop, token, evset);
+    }
+
+    /// Called for each message received through a `Sender`.
+    fn notify(&mut self, event_loop: &mut EventLoop<Self>, msg: Self::Message) {
+        let _ = (event_loop, msg);
+    }
+
+    /// Called when a timeout set with `EventLoop::timeout` expires.
+    fn timeout(&mut self, event_loop: &mut EventLoop<Self>, token: Token) {
+        let _ = (event_loop, token);
+    }
+
+    /// Called at the end of each iteration of the loop, after all other callbacks.
+    fn tick(&mut self, event_loop: &mut EventLoop<Self>) {
+        let _ = event_loop;
+    }
+}
+
+/// Drives a `Handler` from the events of a `Selector`.
+pub struct EventLoop<H: Handler> {
+    selector: Selector,
+    running: bool,
+    waker: Waker,
+    tx: mpsc::Sender<H::Message>,
+    rx: mpsc::Receiver<H::Message>,
+    // Reused between iterations so that callbacks can borrow the loop mutably.
+    events: Vec<Fired>,
+}
+
+impl<H: Handler> EventLoop<H> {
+    /// Creates an `EventLoop` using a `Selector` with the default backend.
+    pub fn new() -> Result<EventLoop<H>> {
+        EventLoop::with_selector(try!(Selector::new()))
+    }
+
+    /// Creates an `EventLoop` driven by the given `Selector`.
+    pub fn with_selector(mut selector: Selector) -> Result<EventLoop<H>> {
+        let waker = try!(Waker::new(&mut selector));
+        let (tx, rx) = mpsc::channel();
+
+        Ok(EventLoop {
+            selector: selector,
+            running: false,
+            waker: waker,
+            tx: tx,
+            rx: rx,
+            events: Vec::new(),
+        })
+    }
+
+    /// Returns a `Sender` for delivering messages to the `Handler` from any thread.
+    pub fn channel(&self) -> Sender<H::Message> {
+        Sender {
+            tx: self.tx.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+
+    /// Registers an `Evented` source with the underlying `Selector`.
+    pub fn register<E: ?Sized>(&mut self,
+                               io: &E,
+                               token: Token,
+                               evset: EventSet,
+                               opts: PollOpt)
+                               -> Result<()>
+        where E: Evented
+    {
+        io.register(&mut self.selector, token, evset, opts)
+    }
+
+    /// Re-registers an `Evented` source with the underlying `Selector`.
+    pub fn reregister<E: ?Sized>(&mut self,
+                                 io: &E,
+                                 token: Token,
+                                 evset: EventSet,
+                                 opts: PollOpt)
+                                 -> Result<()>
+        where E: Evented
+    {
+        io.reregister(&mut self.selector, token, evset, opts)
+    }
+
+    /// Deregisters an `Evented` source with the underlying `Selector`.
+    pub fn deregister<E: ?Sized>(&mut self, io: &E) -> Result<()>
+        where E: Evented
+    {
+        io.deregister(&mut self.selector)
+    }
+
+    /// Schedules a call to `Handler::timeout` with `token` once `delay` has elapsed.
+    pub fn timeout(&mut self, token: Token, delay: Duration) -> Timeout {
+        self.selector.set_timeout(token, delay)
+    }
+
+    /// Cancels a pending timeout, returning `false` if it already expired or was cancelled.
+    pub fn clear_timeout(&mut self, timeout: Timeout) -> bool {
+        self.selector.cancel_timeout(timeout)
+    }
+
+    /// Returns the underlying `Selector`.
+    pub fn selector(&mut self) -> &mut Selector {
+        &mut self.selector
+    }
+
+    /// Returns `true` if the loop is inside `run` and has not been shut down.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Stops the loop once the current iteration completes.
+    pub fn shutdown(&mut self) {
+        self.running = false;
+    }
+
+    /// Runs the loop until `shutdown` is called.
+    pub fn run(&mut self, handler: &mut H) -> Result<()> {
+        self.run_with(handler, |_, _, _| false)
+    }
+
+    /// Runs a single iteration of the loop, waiting up to `timeout` for events.
+    ///
+    /// Every event returned by the `Selector` is dispatched to `handler`, followed by a call to
+    /// `Handler::tick`.
+    pub fn run_once(&mut self, handler: &mut H, timeout: Option<Duration>) -> Result<()> {
+        self.run_once_with(handler, timeout, &mut |_, _, _| false)
+    }
+
+    // Runs the loop like `run`, first offering each event to `intercept`, which returns `true` if
+    // it consumed the event and it is not to be dispatched to `handler`.
+    pub(crate) fn run_with<F>(&mut self, handler: &mut H, mut intercept: F) -> Result<()>
+        where F: FnMut(&mut EventLoop<H>, &mut H, &Fired) -> bool
+    {
+        self.running = true;
+
+        while self.running {
+            if let Err(err) = self.run_once_with(handler, None, &mut intercept) {
+                self.running = false;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_once_with<F>(&mut self,
+                        handler: &mut H,
+                        timeout: Option<Duration>,
+                        intercept: &mut F)
+                        -> Result<()>
+        where F: FnMut(&mut EventLoop<H>, &mut H, &Fired) -> bool
+    {
+        let mut events = mem::replace(&mut self.events, Vec::new());
+
+        match self.selector.poll(timeout) {
+            Ok(fired) => events.extend(fired),
+            Err(err) => {
+                self.events = events;
+                return Err(err);
+            }
+        }
+
+        for fired in events.drain(..) {
+            if intercept(self, handler, &fired) {
+                continue;
+            }
+
+            if fired.is_wakeup() {
+                self.notify(handler);
+            } else if fired.is_timeout() {
+                handler.timeout(self, fired.token());
+            } else {
+                handler.ready(self, fired.token(), fired.evset());
+            }
+        }
+        self.events = events;
+
+        handler.tick(self);
+
+        Ok(())
+    }
+
+    // Delivers the messages queued when the waker fired. Messages sent from within `notify` wake
+    // the loop again and are delivered on the next iteration.
+    fn notify(&mut self, handler: &mut H) {
+        let msgs = self.rx.try_iter().collect::<Vec<_>>();
+        for msg in msgs {
+            handler.notify(self, msg);
+        }
+    }
+}
+
+impl<H: Handler> fmt::Debug for EventLoop<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventLoop")
+            .field("selector", &self.selector)
+            .field("running", &self.running)
+            .finish()
+    }
+}
+
+/// Sends messages to the `Handler` of an `EventLoop`, waking it if necessary.
+pub struct Sender<M> {
+    tx: mpsc::Sender<M>,
+    waker: Waker,
+}
+
+impl<M> Sender<M> {
+    /// Queues `msg` for delivery to `Handler::notify`.
+    ///
+    /// Fails if the `EventLoop` has been dropped.
+    pub fn send(&self, msg: M) -> Result<()> {
+        if self.tx.send(msg).is_err() {
+            return Err(Error::new(ErrorKind::BrokenPipe, "event loop has been dropped"));
+        }
+
+        self.waker.wake()
+    }
+}
+
+impl<M> Clone for Sender<M> {
+    fn clone(&self) -> Sender<M> {
+        Sender {
+            tx: self.tx.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+}
+
+impl<M> fmt::Debug for Sender<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("waker", &self.waker)
+            .finish()
+    }
+}
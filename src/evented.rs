@@ -0,0 +1,108 @@
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::os::unix::net::{UnixStream, UnixListener, UnixDatagram};
+use std::net::{TcpStream, TcpListener, UdpSocket};
+use std::io::Result;
+
+use event::{EventSet, PollOpt};
+use selector::Selector;
+use signal::Signals;
+use token::Token;
+
+/// A source of events that can be registered with a `Selector`.
+///
+/// Implemented for the standard socket types and the event sources of this crate. Types composed
+/// of several sources can implement it by forwarding to each of their parts.
+pub trait Evented {
+    fn register(&self,
+                selector: &mut Selector,
+                token: Token,
+                evset: EventSet,
+                opts: PollOpt)
+                -> Result<()>;
+
+    fn reregister(&self,
+                  selector: &mut Selector,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()>;
+
+    fn deregister(&self, selector: &mut Selector) -> Result<()>;
+}
+
+/// Adapts a borrowed raw file descriptor to `Evented`.
+///
+/// The file descriptor must remain open for as long as it is registered.
+#[derive(Debug, Clone, Copy)]
+pub struct EventedFd<'a>(pub &'a RawFd);
+
+impl<'a> Evented for EventedFd<'a> {
+    fn register(&self,
+                selector: &mut Selector,
+                token: Token,
+                evset: EventSet,
+                opts: PollOpt)
+                -> Result<()> {
+        selector.register(*self.0, token, evset, opts)
+    }
+
+    fn reregister(&self,
+                  selector: &mut Selector,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        selector.reregister(*self.0, token, evset, opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> Result<()> {
+        selector.deregister(*self.0)
+    }
+}
+
+// Implements `Evented` for a type by registering its raw file descriptor.
+macro_rules! evented_fd {
+    ($($ty:ty),*) => {
+        $(
+            impl Evented for $ty {
+                fn register(&self,
+                            selector: &mut Selector,
+                            token: Token,
+                            evset: EventSet,
+                            opts: PollOpt)
+                            -> Result<()> {
+                    EventedFd(&self.as_raw_fd()).register(selector, token, evset, opts)
+                }
+
+                fn reregister(&self,
+                              selector: &mut Selector,
+                              token: Token,
+                              evset: EventSet,
+                              opts: PollOpt)
+                              -> Result<()> {
+                    EventedFd(&self.as_raw_fd()).reregister(selector, token, evset, opts)
+                }
+
+                fn deregister(&self, selector: &mut Selector) -> Result<()> {
+                    EventedFd(&self.as_raw_fd()).deregister(selector)
+                }
+            }
+        )*
+    }
+}
+
+evented_fd!(TcpStream, TcpListener, UdpSocket, UnixStream, UnixListener, UnixDatagram, Signals);
+
+evented_fd!(::net::TcpStream,
+            ::net::TcpListener,
+            ::net::UdpSocket,
+            ::net::UnixStream,
+            ::net::UnixListener,
+            ::net::UnixDatagram,
+            ::net::UnixSeqpacket,
+            ::net::UnixSeqpacketListener);
+
+evented_fd!(::pipe::Sender, ::pipe::Receiver);
+
+#[cfg(target_os = "linux")]
+evented_fd!(::timer::Timer);
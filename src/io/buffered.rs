@@ -0,0 +1,256 @@
+use std::io::prelude::*;
+use std::io::{IoSlice, IoSliceMut, Result, Error, ErrorKind};
+use std::cmp;
+
+use super::ring::RingBuf;
+use super::{ReadExt, WriteExt, Status};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+const DEFAULT_LOW_WATERMARK: usize = 16 * 1024;
+const DEFAULT_HIGH_WATERMARK: usize = 64 * 1024;
+
+/// Buffers input from a nonblocking reader.
+///
+/// Each `fill` reads as much as is available into a ring buffer, which grows when full, and the
+/// caller inspects and consumes the buffered bytes at its own pace.
+#[derive(Debug)]
+pub struct NbBufReader<R> {
+    inner: R,
+    buf: RingBuf,
+}
+
+impl<R: Read> NbBufReader<R> {
+    pub fn new(inner: R) -> NbBufReader<R> {
+        NbBufReader::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> NbBufReader<R> {
+        NbBufReader {
+            inner: inner,
+            buf: RingBuf::with_capacity(capacity),
+        }
+    }
+
+    /// Reads from the inner reader into the free space of the buffer, growing it first if it is
+    /// full, and returns the number of bytes read and why reading stopped.
+    ///
+    /// `BufferFull` means the buffer filled before the reader would block, so more may be
+    /// available.
+    pub fn fill(&mut self) -> Result<(usize, Status)> {
+        if self.buf.is_full() {
+            let capacity = self.buf.capacity();
+            self.buf.reserve(if capacity == 0 { DEFAULT_CAPACITY } else { capacity });
+        }
+
+        let res = {
+            let (a, b) = self.buf.free_slices_mut();
+            self.inner.read_nb_vectored(&mut [IoSliceMut::new(a), IoSliceMut::new(b)])
+        };
+
+        if let Ok((n, _)) = res {
+            self.buf.commit(n);
+        }
+        res
+    }
+
+    /// Returns the buffered bytes.
+    pub fn buffer(&mut self) -> &[u8] {
+        self.buf.make_contiguous()
+    }
+
+    /// Discards the first `n` buffered bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if fewer than `n` bytes are buffered.
+    pub fn consume(&mut self, n: usize) {
+        self.buf.consume(n)
+    }
+
+    /// The number of bytes buffered.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps the inner reader. Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for NbBufReader<R> {
+    /// Reads buffered bytes, filling the buffer first if it is empty.
+    ///
+    /// Returns an error of kind `WouldBlock` if nothing is buffered or available.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.buf.is_empty() {
+            if let (0, Status::Drained) = try!(self.fill()) {
+                return Err(Error::new(ErrorKind::WouldBlock, "no data available"));
+            }
+        }
+
+        let n = {
+            let (a, b) = self.buf.as_slices();
+            let first = cmp::min(a.len(), buf.len());
+            let second = cmp::min(b.len(), buf.len() - first);
+            buf[..first].copy_from_slice(&a[..first]);
+            buf[first..first + second].copy_from_slice(&b[..second]);
+            first + second
+        };
+        self.buf.consume(n);
+
+        Ok(n)
+    }
+}
+
+/// Queues output for a nonblocking writer.
+///
+/// Bytes written are queued in a ring buffer and written out with `flush_nb` as the inner writer
+/// accepts them, typically each time it is reported writable. Writes are refused once the queue
+/// reaches the high watermark; producers should then wait until `flush_nb` brings it below the
+/// low watermark.
+#[derive(Debug)]
+pub struct NbBufWriter<W> {
+    inner: W,
+    buf: RingBuf,
+    low: usize,
+    high: usize,
+}
+
+impl<W: Write> NbBufWriter<W> {
+    pub fn new(inner: W) -> NbBufWriter<W> {
+        NbBufWriter::with_watermarks(DEFAULT_LOW_WATERMARK, DEFAULT_HIGH_WATERMARK, inner)
+    }
+
+    /// Creates an `NbBufWriter` with the given low and high watermarks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is greater than `high`.
+    pub fn with_watermarks(low: usize, high: usize, inner: W) -> NbBufWriter<W> {
+        assert!(low <= high, "low watermark above high watermark");
+
+        NbBufWriter {
+            inner: inner,
+            buf: RingBuf::with_capacity(DEFAULT_CAPACITY),
+            low: low,
+            high: high,
+        }
+    }
+
+    /// Sets the low and high watermarks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is greater than `high`.
+    pub fn set_watermarks(&mut self, low: usize, high: usize) {
+        assert!(low <= high, "low watermark above high watermark");
+
+        self.low = low;
+        self.high = high;
+    }
+
+    pub fn low_watermark(&self) -> usize {
+        self.low
+    }
+
+    pub fn high_watermark(&self) -> usize {
+        self.high
+    }
+
+    /// Returns `true` if the queue has reached the high watermark, and writes will be refused.
+    pub fn above_high_watermark(&self) -> bool {
+        self.buf.len() >= self.high
+    }
+
+    /// Returns `true` if the queue has drained to the low watermark, and producers may resume.
+    pub fn below_low_watermark(&self) -> bool {
+        self.buf.len() <= self.low
+    }
+
+    /// Queues all of `buf` regardless of the watermarks, as for a message that must not be split.
+    pub fn queue(&mut self, buf: &[u8]) {
+        self.buf.extend(buf);
+    }
+
+    /// Writes queued bytes to the inner writer until the queue is empty or the writer would
+    /// block, returning the number of bytes written and why writing stopped.
+    pub fn flush_nb(&mut self) -> Result<(usize, Status)> {
+        let res = {
+            let (a, b) = self.buf.as_slices();
+            self.inner.write_nb_vectored(&[IoSlice::new(a), IoSlice::new(b)])
+        };
+
+        if let Ok((n, _)) = res {
+            self.buf.consume(n);
+        }
+        res
+    }
+
+    /// Returns `true` while bytes remain queued, and so the inner writer's writable readiness is
+    /// still of interest.
+    pub fn wants_writable(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    /// The number of bytes queued.
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps the inner writer. Any queued bytes are lost.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for NbBufWriter<W> {
+    /// Queues as much of `buf` as fits below the high watermark.
+    ///
+    /// Returns an error of kind `WouldBlock` if the queue is already at the high watermark.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.above_high_watermark() && !buf.is_empty() {
+            return Err(Error::new(ErrorKind::WouldBlock, "write queue above high watermark"));
+        }
+
+        let n = cmp::min(buf.len(), self.high.saturating_sub(self.buf.len()));
+        self.buf.extend(&buf[..n]);
+        Ok(n)
+    }
+
+    /// Writes out the queue.
+    ///
+    /// Returns an error of kind `WouldBlock` if the inner writer would block before the queue
+    /// empties.
+    fn flush(&mut self) -> Result<()> {
+        match try!(self.flush_nb()) {
+            (_, Status::Drained) => self.inner.flush(),
+            (_, Status::BufferFull) => Err(Error::new(ErrorKind::WouldBlock, "queue not flushed")),
+            (_, Status::Eof) => Err(Error::new(ErrorKind::WriteZero, "failed to write queue")),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::prelude::*;
+use std::io::{IoSlice, IoSliceMut, Result, ErrorKind};
+
+pub(crate) mod ring;
+mod buffered;
+pub use self::buffered::{NbBufReader, NbBufWriter};
+#[cfg(target_os = "linux")]
+mod transfer;
+#[cfg(target_os = "linux")]
+pub use self::transfer::Transfer;
+
+/// Why a nonblocking read or write loop stopped.
+///
+/// `Drained` means the source ran out of bytes for now, and `BufferFull` that the destination had
+/// no room for more. For `read_nb` the source is the reader and the destination is the buffer, so
+/// `Drained` means the read would block. For `write_nb` the source is the buffer, so `Drained`
+/// means all of it was written and `BufferFull` that the write would block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// The source has no more bytes for now.
+    Drained,
+    /// The peer has closed its end; no more bytes will be transferred.
+    Eof,
+    /// The destination has no room for more bytes.
+    BufferFull,
+}
+
+pub trait ReadExt: Read {
+    /// Reads into `buf` until it is full or the reader would block or reaches end-of-file,
+    /// returning the number of bytes read and which of these stopped it.
+    fn read_nb(&mut self, buf: &mut [u8]) -> Result<(usize, Status)> {
+        let mut nread = 0;
+
+        loop {
+            if nread == buf.len() {
+                return Ok((nread, Status::BufferFull));
+            }
+
+            match self.read(&mut buf[nread..]) {
+                Ok(0) => return Ok((nread, Status::Eof)),
+                Ok(n) => nread += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok((nread, Status::Drained))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Like `read_nb`, but fills each buffer of `bufs` in turn.
+    ///
+    /// Each call reads into as many buffers as it can, using `readv(2)` where the reader supports
+    /// it.
+    fn read_nb_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<(usize, Status)> {
+        let total = bufs.iter().map(|buf| buf.len()).sum();
+        let mut nread = 0;
+
+        loop {
+            if nread == total {
+                return Ok((nread, Status::BufferFull));
+            }
+
+            // A partly filled buffer is finished on its own before moving on to the rest.
+            let res = match position(bufs.iter().map(|buf| buf.len()), nread) {
+                (i, 0) => self.read_vectored(&mut bufs[i..]),
+                (i, offset) => self.read(&mut bufs[i][offset..]),
+            };
+
+            match res {
+                Ok(0) => return Ok((nread, Status::Eof)),
+                Ok(n) => nread += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok((nread, Status::Drained))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<T> ReadExt for T where T: Read {}
+
+pub trait WriteExt: Write {
+    /// Writes from `buf` until all of it is written or the writer would block or accepts no more,
+    /// returning the number of bytes written and which of these stopped it.
+    fn write_nb(&mut self, buf: &[u8]) -> Result<(usize, Status)> {
+        let mut nwrit: usize = 0;
+
+        loop {
+            if nwrit == buf.len() {
+                return Ok((nwrit, Status::Drained));
+            }
+
+            match self.write(&buf[nwrit..]) {
+                Ok(0) => return Ok((nwrit, Status::Eof)),
+                Ok(n) => nwrit += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok((nwrit, Status::BufferFull))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Like `write_nb`, but writes each buffer of `bufs` in turn.
+    ///
+    /// Each call writes from as many buffers as it can, using `writev(2)` where the writer
+    /// supports it.
+    fn write_nb_vectored(&mut self, bufs: &[IoSlice]) -> Result<(usize, Status)> {
+        let total = bufs.iter().map(|buf| buf.len()).sum();
+        let mut nwrit = 0;
+
+        loop {
+            if nwrit == total {
+                return Ok((nwrit, Status::Drained));
+            }
+
+            let res = match position(bufs.iter().map(|buf| buf.len()), nwrit) {
+                (i, 0) => self.write_vectored(&bufs[i..]),
+                (i, offset) => self.write(&bufs[i][offset..]),
+            };
+
+            match res {
+                Ok(0) => return Ok((nwrit, Status::Eof)),
+                Ok(n) => nwrit += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok((nwrit, Status::BufferFull))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<T> WriteExt for T where T: Write {}
+
+// Returns the index of the buffer containing byte `n` of the concatenated buffers, skipping empty
+// ones, and the offset of that byte within it.
+fn position<I>(lens: I, mut n: usize) -> (usize, usize)
+    where I: Iterator<Item = usize>
+{
+    let mut index = 0;
+    for len in lens {
+        if n < len {
+            break;
+        }
+        n -= len;
+        index += 1;
+    }
+
+    (index, n)
+}
@@ -0,0 +1,123 @@
+use std::cmp;
+
+// A growable ring buffer of bytes.
+//
+// The buffered bytes start at `head` and may wrap around the end of `buf`, so they are exposed as
+// up to two slices, as is the free space after them.
+#[derive(Debug)]
+pub struct RingBuf {
+    buf: Vec<u8>,
+    head: usize,
+    len: usize,
+}
+
+impl RingBuf {
+    pub fn with_capacity(capacity: usize) -> RingBuf {
+        RingBuf {
+            buf: vec![0; capacity],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.buf.len()
+    }
+
+    // The buffered bytes, in order.
+    pub fn as_slices(&self) -> (&[u8], &[u8]) {
+        let end = self.head + self.len;
+
+        if end <= self.buf.len() {
+            (&self.buf[self.head..end], &[])
+        } else {
+            (&self.buf[self.head..], &self.buf[..end - self.buf.len()])
+        }
+    }
+
+    // The free space following the buffered bytes, in order. Bytes written here are added to the
+    // buffer by `commit`.
+    pub fn free_slices_mut(&mut self) -> (&mut [u8], &mut [u8]) {
+        if self.is_full() {
+            return (&mut [], &mut []);
+        }
+
+        let tail = (self.head + self.len) % self.buf.len();
+        if tail < self.head {
+            (&mut self.buf[tail..self.head], &mut [])
+        } else {
+            let (front, back) = self.buf.split_at_mut(tail);
+            (back, &mut front[..self.head])
+        }
+    }
+
+    pub fn commit(&mut self, n: usize) {
+        assert!(n <= self.buf.len() - self.len);
+        self.len += n;
+    }
+
+    pub fn consume(&mut self, n: usize) {
+        assert!(n <= self.len);
+        if n == self.len {
+            self.head = 0;
+            self.len = 0;
+        } else {
+            self.head = (self.head + n) % self.buf.len();
+            self.len -= n;
+        }
+    }
+
+    // Grows the buffer so that at least `additional` more bytes fit.
+    pub fn reserve(&mut self, additional: usize) {
+        if self.buf.len() - self.len >= additional {
+            return;
+        }
+
+        let capacity = cmp::max(self.buf.len() * 2, self.len + additional);
+        let mut buf = Vec::with_capacity(capacity);
+        {
+            let (a, b) = self.as_slices();
+            buf.extend_from_slice(a);
+            buf.extend_from_slice(b);
+        }
+        buf.resize(capacity, 0);
+
+        self.buf = buf;
+        self.head = 0;
+    }
+
+    pub fn extend(&mut self, data: &[u8]) {
+        self.reserve(data.len());
+
+        {
+            let (a, b) = self.free_slices_mut();
+            let split = cmp::min(a.len(), data.len());
+            a[..split].copy_from_slice(&data[..split]);
+            b[..data.len() - split].copy_from_slice(&data[split..]);
+        }
+
+        self.len += data.len();
+    }
+
+    // Moves the buffered bytes to the start of the buffer, returning them as one slice.
+    pub fn make_contiguous(&mut self) -> &[u8] {
+        if self.head + self.len > self.buf.len() {
+            self.buf.rotate_left(self.head);
+            self.head = 0;
+        }
+
+        &self.buf[self.head..self.head + self.len]
+    }
+}
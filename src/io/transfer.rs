@@ -0,0 +1,157 @@
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::io::{Result, ErrorKind};
+use std::cmp;
+use std::mem;
+use std::ptr;
+
+use libc;
+
+use cvt;
+use pipe::Pipe;
+use super::Status;
+
+// The most the kernel will transfer in one call; larger counts make `splice(2)` fail on some
+// descriptors.
+const MAX_COUNT: usize = 0x7fff_f000;
+
+/// Moves bytes from one file descriptor to another without copying them through user space.
+///
+/// Sockets and other descriptors are joined with `splice(2)` through an internal pipe. Bytes
+/// taken from the source but not yet accepted by the destination stay in the pipe until the next
+/// call, so one `Transfer` should be kept for each direction being forwarded. A regular file as
+/// the source is sent with `sendfile(2)` instead, from and advancing its current offset.
+#[derive(Debug)]
+pub struct Transfer {
+    pipe: Pipe,
+    pending: usize,
+}
+
+impl Transfer {
+    /// Creates a `Transfer` whose internal pipe has the default capacity.
+    pub fn new() -> Result<Transfer> {
+        Ok(Transfer {
+            pipe: try!(Pipe::new()),
+            pending: 0,
+        })
+    }
+
+    /// Creates a `Transfer` whose internal pipe holds at least `capacity` bytes, bounding how
+    /// much each call to `splice(2)` moves.
+    pub fn with_capacity(capacity: usize) -> Result<Transfer> {
+        let transfer = try!(Transfer::new());
+        try!(transfer.pipe.set_capacity(capacity));
+        Ok(transfer)
+    }
+
+    /// The number of bytes taken from a source but not yet written to a destination.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Moves up to `len` bytes from `src` to `dst`, returning the number written to `dst` and why
+    /// the transfer stopped, as for `WriteExt::write_nb`.
+    ///
+    /// Pending bytes are written first. Then `Drained` means `src` would block or `len` bytes
+    /// were written, `BufferFull` that `dst` would block, and `Eof` that `src` reached
+    /// end-of-file.
+    pub fn transfer<S, D>(&mut self, src: &S, dst: &D, len: usize) -> Result<(usize, Status)>
+        where S: ?Sized + AsRawFd,
+              D: ?Sized + AsRawFd
+    {
+        let (src, dst) = (src.as_raw_fd(), dst.as_raw_fd());
+
+        let (mut written, full) = try!(self.flush(dst, len));
+        if full {
+            return Ok((written, Status::BufferFull));
+        }
+
+        if try!(is_file(src)) {
+            return sendfile(src, dst, len - written).map(|(n, status)| (written + n, status));
+        }
+
+        loop {
+            if written == len {
+                return Ok((written, Status::Drained));
+            }
+
+            // The pipe is empty, so blocking here means `src` has nothing more for now.
+            match splice(src, self.pipe.sender().as_raw_fd(), len - written) {
+                Ok(0) => return Ok((written, Status::Eof)),
+                Ok(n) => self.pending += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                    return Ok((written, Status::Drained))
+                }
+                Err(e) => return Err(e),
+            }
+
+            let (n, full) = try!(self.flush(dst, len - written));
+            written += n;
+            if full {
+                return Ok((written, Status::BufferFull));
+            }
+        }
+    }
+
+    // Writes up to `len` pending bytes to `dst`, returning the number written and whether `dst`
+    // would block.
+    fn flush(&mut self, dst: RawFd, len: usize) -> Result<(usize, bool)> {
+        let mut written = 0;
+
+        while self.pending > 0 && written < len {
+            let count = cmp::min(self.pending, len - written);
+            match splice(self.pipe.receiver().as_raw_fd(), dst, count) {
+                Ok(n) => {
+                    self.pending -= n;
+                    written += n;
+                }
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => return Ok((written, true)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok((written, false))
+    }
+}
+
+fn sendfile(src: RawFd, dst: RawFd, len: usize) -> Result<(usize, Status)> {
+    let mut written = 0;
+
+    loop {
+        if written == len {
+            return Ok((written, Status::Drained));
+        }
+
+        let count = cmp::min(len - written, MAX_COUNT);
+        match cvt(unsafe { libc::sendfile(dst, src, ptr::null_mut(), count) }) {
+            Ok(0) => return Ok((written, Status::Eof)),
+            Ok(n) => written += n as usize,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                return Ok((written, Status::BufferFull))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn splice(src: RawFd, dst: RawFd, len: usize) -> Result<usize> {
+    let res = unsafe {
+        libc::splice(src,
+                     ptr::null_mut(),
+                     dst,
+                     ptr::null_mut(),
+                     cmp::min(len, MAX_COUNT),
+                     libc::SPLICE_F_NONBLOCK | libc::SPLICE_F_MOVE)
+    };
+
+    cvt(res).map(|n| n as usize)
+}
+
+fn is_file(fd: RawFd) -> Result<bool> {
+    let mut stat: libc::stat = unsafe { mem::zeroed() };
+    try!(cvt(unsafe { libc::fstat(fd, &mut stat) }));
+
+    Ok(stat.st_mode & libc::S_IFMT == libc::S_IFREG)
+}
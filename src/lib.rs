@@ -1,19 +1,48 @@
 #[macro_use]
 extern crate bitflags;
 extern crate libc;
-extern crate time;
 extern crate num;
 extern crate rand;
 
 pub mod selector;
-pub use self::selector::{Selector, Iter, Fired};
+pub use self::selector::{Selector, Iter, Fired, Backend, BackendKind};
 mod event;
-pub use self::event::EventSet;
+pub use self::event::{EventSet, PollOpt};
+mod event_loop;
+pub use self::event_loop::{EventLoop, Handler, Sender};
+mod evented;
+pub use self::evented::{Evented, EventedFd};
+mod token;
+pub use self::token::Token;
+mod waker;
+pub use self::waker::Waker;
+mod wheel;
+pub use self::wheel::Timeout;
+mod pool;
+pub use self::pool::{ThreadPoolLoop, PoolHandler, Distribution, Worker};
+mod reactor;
+pub use self::reactor::{Reactor, Registration, Async};
+mod signal;
+pub use self::signal::{Signals, SigInfo};
+#[cfg(target_os = "linux")]
+mod timer;
+#[cfg(target_os = "linux")]
+pub use self::timer::Timer;
 pub mod io;
+pub mod codec;
+pub mod rt;
+pub mod net;
+pub mod pipe;
+pub use self::pipe::Pipe;
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+pub mod uring;
 
 use std::os::unix::io::{RawFd, AsRawFd};
 use std::io::{Result, Error};
 
+// The most buffers passed to `readv` and `writev` in one call, as on Linux and the BSDs.
+const IOV_MAX: usize = 1024;
+
 pub trait NonBlocking {
     fn set_nonblock(&mut self) -> Result<()>;
     fn set_block(&mut self) -> Result<()>;
@@ -95,6 +124,20 @@ pub unsafe fn set_block(fd: RawFd) -> Result<()> {
     }
 }
 
+pub unsafe fn set_cloexec(fd: RawFd) -> Result<()> {
+    let res = {
+        let mut flags = libc::fcntl(fd, libc::F_GETFD);
+        flags |= libc::FD_CLOEXEC;
+        libc::fcntl(fd, libc::F_SETFD, flags)
+    };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
 pub unsafe fn is_nonblock(fd: RawFd) -> bool {
     let flags = libc::fcntl(fd, libc::F_GETFL);
 
@@ -104,3 +147,12 @@ pub unsafe fn is_nonblock(fd: RawFd) -> bool {
 pub unsafe fn is_block(fd: RawFd) -> bool {
     !is_nonblock(fd)
 }
+
+// Converts the return value of a libc call into a `Result`.
+pub(crate) fn cvt<T: PartialEq + From<i8>>(res: T) -> Result<T> {
+    if res == T::from(-1) {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res)
+    }
+}
@@ -0,0 +1,16 @@
+//! Nonblocking sockets.
+//!
+//! Every socket is created nonblocking and close-on-exec, and is intended to be registered with a
+//! `Selector`. Operations that cannot complete immediately fail with `ErrorKind::WouldBlock`.
+
+mod socket;
+
+mod tcp;
+pub use self::tcp::{TcpListener, TcpStream};
+
+mod udp;
+pub use self::udp::{UdpSocket, RecvMeta};
+
+mod unix;
+pub use self::unix::{UnixAddr, UCred, UnixStream, UnixListener, UnixDatagram, UnixSeqpacket,
+                     UnixSeqpacketListener};
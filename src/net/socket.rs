@@ -0,0 +1,645 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6, Ipv4Addr, Ipv6Addr, Shutdown};
+use std::io::{IoSlice, IoSliceMut, Result, Error, ErrorKind};
+use std::ffi::OsStr;
+use std::cmp;
+use std::mem;
+use std::ptr;
+
+use libc;
+
+use cvt;
+use super::unix::UnixAddr;
+
+// Flags passed to `send` so that writing to a closed connection returns `EPIPE` rather than
+// raising `SIGPIPE`.
+#[cfg(target_os = "linux")]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(target_os = "linux"))]
+const SEND_FLAGS: libc::c_int = 0;
+
+// Flags passed to `recvmsg` so that received file descriptors are close-on-exec from the start.
+#[cfg(target_os = "linux")]
+const RECVMSG_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(target_os = "linux"))]
+const RECVMSG_FLAGS: libc::c_int = 0;
+
+/// An owned socket file descriptor, always nonblocking and close-on-exec.
+#[derive(Debug)]
+pub struct Socket {
+    fd: RawFd,
+}
+
+impl Socket {
+    #[cfg(any(target_os = "linux",
+              target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly"))]
+    pub fn new(family: libc::c_int, ty: libc::c_int) -> Result<Socket> {
+        let fd = unsafe {
+            libc::socket(family, ty | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, 0)
+        };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(Socket { fd: fd })
+    }
+
+    // Without `SOCK_NONBLOCK` the flags are set separately, which races with a concurrent `exec`.
+    #[cfg(not(any(target_os = "linux",
+                  target_os = "freebsd",
+                  target_os = "openbsd",
+                  target_os = "netbsd",
+                  target_os = "bitrig",
+                  target_os = "dragonfly")))]
+    pub fn new(family: libc::c_int, ty: libc::c_int) -> Result<Socket> {
+        let fd = unsafe { libc::socket(family, ty, 0) };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        let socket = Socket { fd: fd };
+        try!(unsafe { ::set_nonblock(fd) });
+        try!(unsafe { ::set_cloexec(fd) });
+
+        Ok(socket)
+    }
+
+    #[cfg(any(target_os = "linux",
+              target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly"))]
+    pub fn pair(family: libc::c_int, ty: libc::c_int) -> Result<(Socket, Socket)> {
+        let mut fds = [0 as libc::c_int; 2];
+        try!(cvt(unsafe {
+            libc::socketpair(family,
+                             ty | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                             0,
+                             fds.as_mut_ptr())
+        }));
+
+        Ok((Socket { fd: fds[0] }, Socket { fd: fds[1] }))
+    }
+
+    #[cfg(not(any(target_os = "linux",
+                  target_os = "freebsd",
+                  target_os = "openbsd",
+                  target_os = "netbsd",
+                  target_os = "bitrig",
+                  target_os = "dragonfly")))]
+    pub fn pair(family: libc::c_int, ty: libc::c_int) -> Result<(Socket, Socket)> {
+        let mut fds = [0 as libc::c_int; 2];
+        try!(cvt(unsafe { libc::socketpair(family, ty, 0, fds.as_mut_ptr()) }));
+
+        let pair = (Socket { fd: fds[0] }, Socket { fd: fds[1] });
+        for &fd in &fds {
+            try!(unsafe { ::set_nonblock(fd) });
+            try!(unsafe { ::set_cloexec(fd) });
+        }
+
+        Ok(pair)
+    }
+
+    /// Creates a socket for the address family of `addr`.
+    pub fn for_addr(addr: &SocketAddr, ty: libc::c_int) -> Result<Socket> {
+        let family = match *addr {
+            SocketAddr::V4(..) => libc::AF_INET,
+            SocketAddr::V6(..) => libc::AF_INET6,
+        };
+
+        Socket::new(family, ty)
+    }
+
+    pub fn bind(&self, addr: &libc::sockaddr_storage, len: libc::socklen_t) -> Result<()> {
+        let res = unsafe {
+            libc::bind(self.fd,
+                       addr as *const libc::sockaddr_storage as *const libc::sockaddr,
+                       len)
+        };
+
+        cvt(res).map(|_| ())
+    }
+
+    pub fn listen(&self, backlog: libc::c_int) -> Result<()> {
+        cvt(unsafe { libc::listen(self.fd, backlog) }).map(|_| ())
+    }
+
+    /// Starts connecting to `addr`, returning once the connection is in progress.
+    pub fn connect(&self, addr: &libc::sockaddr_storage, len: libc::socklen_t) -> Result<()> {
+        let res = unsafe {
+            libc::connect(self.fd,
+                          addr as *const libc::sockaddr_storage as *const libc::sockaddr,
+                          len)
+        };
+
+        match cvt(res) {
+            Err(ref err) if err.raw_os_error() == Some(libc::EINPROGRESS) => Ok(()),
+            Err(err) => Err(err),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    #[cfg(any(target_os = "linux",
+              target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly"))]
+    pub fn accept(&self) -> Result<(Socket, libc::sockaddr_storage, libc::socklen_t)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        let fd = try!(retry(|| unsafe {
+            libc::accept4(self.fd,
+                          &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                          &mut len,
+                          libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC)
+        }));
+
+        Ok((Socket { fd: fd }, storage, len))
+    }
+
+    #[cfg(not(any(target_os = "linux",
+                  target_os = "freebsd",
+                  target_os = "openbsd",
+                  target_os = "netbsd",
+                  target_os = "bitrig",
+                  target_os = "dragonfly")))]
+    pub fn accept(&self) -> Result<(Socket, libc::sockaddr_storage, libc::socklen_t)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        let fd = try!(retry(|| unsafe {
+            libc::accept(self.fd,
+                         &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                         &mut len)
+        }));
+
+        let socket = Socket { fd: fd };
+        try!(unsafe { ::set_nonblock(fd) });
+        try!(unsafe { ::set_cloexec(fd) });
+
+        Ok((socket, storage, len))
+    }
+
+    pub fn local_addr(&self) -> Result<(libc::sockaddr_storage, libc::socklen_t)> {
+        self.name(libc::getsockname)
+    }
+
+    pub fn peer_addr(&self) -> Result<(libc::sockaddr_storage, libc::socklen_t)> {
+        self.name(libc::getpeername)
+    }
+
+    fn name(&self,
+            f: unsafe extern "C" fn(libc::c_int, *mut libc::sockaddr, *mut libc::socklen_t)
+                                    -> libc::c_int)
+            -> Result<(libc::sockaddr_storage, libc::socklen_t)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        try!(cvt(unsafe {
+            f(self.fd,
+              &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+              &mut len)
+        }));
+
+        Ok((storage, len))
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        let how = match how {
+            Shutdown::Read => libc::SHUT_RD,
+            Shutdown::Write => libc::SHUT_WR,
+            Shutdown::Both => libc::SHUT_RDWR,
+        };
+
+        cvt(unsafe { libc::shutdown(self.fd, how) }).map(|_| ())
+    }
+
+    pub fn setsockopt<T>(&self, level: libc::c_int, name: libc::c_int, val: T) -> Result<()> {
+        let res = unsafe {
+            libc::setsockopt(self.fd,
+                             level,
+                             name,
+                             &val as *const T as *const libc::c_void,
+                             mem::size_of::<T>() as libc::socklen_t)
+        };
+
+        cvt(res).map(|_| ())
+    }
+
+    pub fn getsockopt<T: Copy>(&self, level: libc::c_int, name: libc::c_int) -> Result<T> {
+        let mut val: T = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<T>() as libc::socklen_t;
+
+        try!(cvt(unsafe {
+            libc::getsockopt(self.fd,
+                             level,
+                             name,
+                             &mut val as *mut T as *mut libc::c_void,
+                             &mut len)
+        }));
+
+        Ok(val)
+    }
+
+    /// Returns and clears the pending error on the socket, such as the outcome of a `connect`.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        let err: libc::c_int = try!(self.getsockopt(libc::SOL_SOCKET, libc::SO_ERROR));
+
+        if err == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Error::from_raw_os_error(err)))
+        }
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        retry(|| unsafe {
+            libc::recv(self.fd,
+                       buf.as_mut_ptr() as *mut libc::c_void,
+                       buf.len() as libc::size_t,
+                       0)
+        })
+            .map(|n| n as usize)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> Result<usize> {
+        retry(|| unsafe {
+            libc::send(self.fd,
+                       buf.as_ptr() as *const libc::c_void,
+                       buf.len() as libc::size_t,
+                       SEND_FLAGS)
+        })
+            .map(|n| n as usize)
+    }
+
+    pub fn recv_vectored(&self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        retry(|| unsafe {
+            libc::readv(self.fd,
+                        bufs.as_ptr() as *const libc::iovec,
+                        cmp::min(bufs.len(), ::IOV_MAX) as libc::c_int)
+        })
+            .map(|n| n as usize)
+    }
+
+    // Uses `sendmsg` rather than `writev` so that `SEND_FLAGS` apply.
+    pub fn send_vectored(&self, bufs: &[IoSlice]) -> Result<usize> {
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+        msg.msg_iovlen = cmp::min(bufs.len(), ::IOV_MAX) as _;
+
+        retry(|| unsafe { libc::sendmsg(self.fd, &msg, SEND_FLAGS) }).map(|n| n as usize)
+    }
+
+    pub fn recv_from(&self,
+                     buf: &mut [u8])
+                     -> Result<(usize, libc::sockaddr_storage, libc::socklen_t)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+
+        let n = try!(retry(|| unsafe {
+            libc::recvfrom(self.fd,
+                           buf.as_mut_ptr() as *mut libc::c_void,
+                           buf.len() as libc::size_t,
+                           0,
+                           &mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+                           &mut len)
+        }));
+
+        Ok((n as usize, storage, len))
+    }
+
+    pub fn send_to(&self,
+                   buf: &[u8],
+                   addr: &libc::sockaddr_storage,
+                   len: libc::socklen_t)
+                   -> Result<usize> {
+        retry(|| unsafe {
+            libc::sendto(self.fd,
+                         buf.as_ptr() as *const libc::c_void,
+                         buf.len() as libc::size_t,
+                         SEND_FLAGS,
+                         addr as *const libc::sockaddr_storage as *const libc::sockaddr,
+                         len)
+        })
+            .map(|n| n as usize)
+    }
+
+    /// Sends `buf` along with the file descriptors in `fds` as `SCM_RIGHTS` ancillary data.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> Result<usize> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+
+        let data_len = fds.len() * mem::size_of::<libc::c_int>();
+        let mut control = control_buf(data_len);
+        if !fds.is_empty() {
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = (control.len() * mem::size_of::<u64>()) as _;
+
+            unsafe {
+                let cmsg = libc::CMSG_FIRSTHDR(&msg);
+                (*cmsg).cmsg_level = libc::SOL_SOCKET;
+                (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+                (*cmsg).cmsg_len = libc::CMSG_LEN(data_len as libc::c_uint) as _;
+                ptr::copy_nonoverlapping(fds.as_ptr(),
+                                         libc::CMSG_DATA(cmsg) as *mut libc::c_int,
+                                         fds.len());
+            }
+        }
+
+        retry(|| unsafe { libc::sendmsg(self.fd, &msg, SEND_FLAGS) }).map(|n| n as usize)
+    }
+
+    /// Receives into `buf` along with up to `fds.len()` file descriptors, returning the number of
+    /// bytes and descriptors received.
+    ///
+    /// Received descriptors are set nonblocking and close-on-exec. Descriptors beyond the space
+    /// in `fds` are closed.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd]) -> Result<(usize, usize)> {
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+
+        let mut control = control_buf(fds.len() * mem::size_of::<libc::c_int>());
+        if !fds.is_empty() {
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = (control.len() * mem::size_of::<u64>()) as _;
+        }
+
+        let n = try!(retry(|| unsafe { libc::recvmsg(self.fd, &mut msg, RECVMSG_FLAGS) }));
+
+        let mut nfds = 0;
+        let mut excess = Vec::new();
+        unsafe {
+            let mut cmsg = if msg.msg_controllen == 0 {
+                ptr::null_mut()
+            } else {
+                libc::CMSG_FIRSTHDR(&msg)
+            };
+
+            while !cmsg.is_null() {
+                if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                    let data = libc::CMSG_DATA(cmsg) as *const libc::c_int;
+                    let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+
+                    for i in 0..len / mem::size_of::<libc::c_int>() {
+                        let fd = ptr::read_unaligned(data.offset(i as isize));
+                        if nfds < fds.len() {
+                            fds[nfds] = fd;
+                            nfds += 1;
+                        } else {
+                            excess.push(fd);
+                        }
+                    }
+                }
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+
+        for fd in excess {
+            let _ = unsafe { libc::close(fd) };
+        }
+        for &fd in &fds[..nfds] {
+            if let Err(err) = set_flags(fd) {
+                for &fd in &fds[..nfds] {
+                    let _ = unsafe { libc::close(fd) };
+                }
+                return Err(err);
+            }
+        }
+
+        Ok((n as usize, nfds))
+    }
+}
+
+// Returns a buffer suitably sized and aligned for a control message carrying `len` bytes.
+fn control_buf(len: usize) -> Vec<u64> {
+    let space = unsafe { libc::CMSG_SPACE(len as libc::c_uint) } as usize;
+    vec![0; (space + mem::size_of::<u64>() - 1) / mem::size_of::<u64>()]
+}
+
+// Makes a received file descriptor nonblocking, and close-on-exec where `recvmsg` could not.
+fn set_flags(fd: RawFd) -> Result<()> {
+    try!(unsafe { ::set_nonblock(fd) });
+    if !cfg!(target_os = "linux") {
+        try!(unsafe { ::set_cloexec(fd) });
+    }
+
+    Ok(())
+}
+
+impl AsRawFd for Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl FromRawFd for Socket {
+    unsafe fn from_raw_fd(fd: RawFd) -> Socket {
+        Socket { fd: fd }
+    }
+}
+
+impl IntoRawFd for Socket {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+        mem::forget(self);
+        fd
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
+
+// The BSDs carry the length of a socket address in its first byte.
+#[cfg(any(target_os = "freebsd",
+          target_os = "openbsd",
+          target_os = "netbsd",
+          target_os = "bitrig",
+          target_os = "dragonfly"))]
+macro_rules! set_len {
+    ($field:expr, $len:expr) => { $field = $len as u8 }
+}
+
+#[cfg(not(any(target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly")))]
+macro_rules! set_len {
+    ($field:expr, $len:expr) => {}
+}
+
+/// Converts an internet socket address into its C representation.
+pub fn from_inet(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+
+    let len = match *addr {
+        SocketAddr::V4(ref addr) => {
+            let sin = unsafe {
+                &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in)
+            };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = addr.port().to_be();
+            sin.sin_addr.s_addr = u32::from(*addr.ip()).to_be();
+            set_len!(sin.sin_len, mem::size_of::<libc::sockaddr_in>());
+
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(ref addr) => {
+            let sin6 = unsafe {
+                &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_in6)
+            };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = addr.port().to_be();
+            sin6.sin6_flowinfo = addr.flowinfo();
+            sin6.sin6_addr.s6_addr = addr.ip().octets();
+            sin6.sin6_scope_id = addr.scope_id();
+            set_len!(sin6.sin6_len, mem::size_of::<libc::sockaddr_in6>());
+
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+
+    (storage, len as libc::socklen_t)
+}
+
+/// Converts the C representation of an internet socket address back.
+pub fn to_inet(storage: &libc::sockaddr_storage) -> Result<SocketAddr> {
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            let sin = unsafe {
+                &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in)
+            };
+            let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+
+            Ok(SocketAddr::V4(SocketAddrV4::new(ip, u16::from_be(sin.sin_port))))
+        }
+        libc::AF_INET6 => {
+            let sin6 = unsafe {
+                &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_in6)
+            };
+            let ip = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+
+            Ok(SocketAddr::V6(SocketAddrV6::new(ip,
+                                                u16::from_be(sin6.sin6_port),
+                                                sin6.sin6_flowinfo,
+                                                sin6.sin6_scope_id)))
+        }
+        _ => Err(Error::new(ErrorKind::InvalidInput, "not an internet socket address")),
+    }
+}
+
+/// Converts a Unix socket address into its C representation.
+pub fn from_unix(addr: &UnixAddr) -> Result<(libc::sockaddr_storage, libc::socklen_t)> {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let sun = unsafe {
+        &mut *(&mut storage as *mut libc::sockaddr_storage as *mut libc::sockaddr_un)
+    };
+    sun.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    let offset = path_offset(sun);
+
+    let len = if let Some(path) = addr.as_path() {
+        let bytes = path.as_os_str().as_bytes();
+        // Leave room for the terminating NUL.
+        if bytes.len() >= sun.sun_path.len() {
+            return Err(Error::new(ErrorKind::InvalidInput, "socket path too long"));
+        }
+        if bytes.contains(&0) {
+            return Err(Error::new(ErrorKind::InvalidInput, "socket path contains a NUL byte"));
+        }
+
+        for (dst, &src) in sun.sun_path.iter_mut().zip(bytes) {
+            *dst = src as libc::c_char;
+        }
+        offset + bytes.len() + 1
+    } else if let Some(name) = addr.as_abstract_name() {
+        if !cfg!(target_os = "linux") {
+            return Err(Error::new(ErrorKind::InvalidInput,
+                                  "abstract socket addresses are only supported on Linux"));
+        }
+        // Abstract names are marked by a leading NUL.
+        if name.len() >= sun.sun_path.len() {
+            return Err(Error::new(ErrorKind::InvalidInput, "abstract socket name too long"));
+        }
+
+        for (dst, &src) in sun.sun_path[1..].iter_mut().zip(name) {
+            *dst = src as libc::c_char;
+        }
+        offset + 1 + name.len()
+    } else {
+        offset
+    };
+    set_len!(sun.sun_len, len);
+
+    Ok((storage, len as libc::socklen_t))
+}
+
+/// Converts the C representation of a Unix socket address back.
+pub fn to_unix(storage: &libc::sockaddr_storage, len: libc::socklen_t) -> Result<UnixAddr> {
+    let sun = unsafe { &*(storage as *const libc::sockaddr_storage as *const libc::sockaddr_un) };
+    let offset = path_offset(sun);
+
+    // Unnamed peers, such as an unbound datagram socket, may not fill in the family at all.
+    let len = len as usize;
+    if len <= offset {
+        return Ok(UnixAddr::unnamed());
+    }
+    if storage.ss_family as libc::c_int != libc::AF_UNIX {
+        return Err(Error::new(ErrorKind::InvalidInput, "not a Unix socket address"));
+    }
+
+    let end = cmp::min(len - offset, sun.sun_path.len());
+    let bytes = sun.sun_path[..end].iter().map(|&c| c as u8).collect::<Vec<_>>();
+
+    if bytes[0] == 0 {
+        if cfg!(target_os = "linux") {
+            Ok(UnixAddr::abstract_name(&bytes[1..]))
+        } else {
+            Ok(UnixAddr::unnamed())
+        }
+    } else {
+        let path = match bytes.iter().position(|&b| b == 0) {
+            Some(nul) => &bytes[..nul],
+            None => &bytes[..],
+        };
+        Ok(UnixAddr::path(OsStr::from_bytes(path)))
+    }
+}
+
+// Returns the offset of `sun_path` within a `sockaddr_un`.
+fn path_offset(sun: &libc::sockaddr_un) -> usize {
+    sun.sun_path.as_ptr() as usize - sun as *const libc::sockaddr_un as usize
+}
+
+// Repeats a call interrupted by a signal.
+pub fn retry<T, F>(mut f: F) -> Result<T>
+    where T: PartialEq + From<i8>,
+          F: FnMut() -> T
+{
+    loop {
+        match cvt(f()) {
+            Err(ref err) if err.kind() == ErrorKind::Interrupted => {}
+            res => return res,
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::net::{SocketAddr, Shutdown};
+use std::io::{Read, Write, IoSlice, IoSliceMut, Result, Error};
+
+use libc;
+
+use super::socket::{self, Socket};
+
+/// A nonblocking TCP socket listening for connections.
+#[derive(Debug)]
+pub struct TcpListener {
+    inner: Socket,
+}
+
+impl TcpListener {
+    /// Creates a `TcpListener` bound to `addr`.
+    ///
+    /// The socket has `SO_REUSEADDR` set, so that a restarted server can rebind its address
+    /// immediately.
+    pub fn bind(addr: &SocketAddr) -> Result<TcpListener> {
+        TcpListener::bind_with(addr, false)
+    }
+
+    /// Creates a `TcpListener` bound to `addr` with `SO_REUSEPORT` set as well.
+    ///
+    /// Several listeners may then bind the same address, and the kernel spreads incoming
+    /// connections between them.
+    pub fn bind_reuseport(addr: &SocketAddr) -> Result<TcpListener> {
+        TcpListener::bind_with(addr, true)
+    }
+
+    fn bind_with(addr: &SocketAddr, reuseport: bool) -> Result<TcpListener> {
+        let inner = try!(Socket::for_addr(addr, libc::SOCK_STREAM));
+        try!(inner.setsockopt(libc::SOL_SOCKET, libc::SO_REUSEADDR, 1 as libc::c_int));
+        if reuseport {
+            try!(inner.setsockopt(libc::SOL_SOCKET, libc::SO_REUSEPORT, 1 as libc::c_int));
+        }
+
+        let (storage, len) = socket::from_inet(addr);
+        try!(inner.bind(&storage, len));
+        try!(inner.listen(1024));
+
+        Ok(TcpListener { inner: inner })
+    }
+
+    /// Accepts a pending connection.
+    ///
+    /// Returns an error of kind `WouldBlock` if there are none.
+    pub fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
+        let (inner, storage, _) = try!(self.inner.accept());
+        let addr = try!(socket::to_inet(&storage));
+
+        Ok((TcpStream { inner: inner }, addr))
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        let (storage, _) = try!(self.inner.local_addr());
+        socket::to_inet(&storage)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl FromRawFd for TcpListener {
+    /// Takes ownership of a listening socket, which must already be nonblocking.
+    unsafe fn from_raw_fd(fd: RawFd) -> TcpListener {
+        TcpListener { inner: Socket::from_raw_fd(fd) }
+    }
+}
+
+impl IntoRawFd for TcpListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
+
+/// A nonblocking TCP connection.
+#[derive(Debug)]
+pub struct TcpStream {
+    inner: Socket,
+}
+
+impl TcpStream {
+    /// Starts connecting to `addr`.
+    ///
+    /// The connection is usually still in progress when this returns. Once the stream becomes
+    /// writable, `take_error` reports whether the connection succeeded.
+    pub fn connect(addr: &SocketAddr) -> Result<TcpStream> {
+        let inner = try!(Socket::for_addr(addr, libc::SOCK_STREAM));
+
+        let (storage, len) = socket::from_inet(addr);
+        try!(inner.connect(&storage, len));
+
+        Ok(TcpStream { inner: inner })
+    }
+
+    pub fn peer_addr(&self) -> Result<SocketAddr> {
+        let (storage, _) = try!(self.inner.peer_addr());
+        socket::to_inet(&storage)
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        let (storage, _) = try!(self.inner.local_addr());
+        socket::to_inet(&storage)
+    }
+
+    /// Shuts down the read half, write half or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        self.inner.shutdown(how)
+    }
+
+    /// Sets `TCP_NODELAY`, disabling Nagle's algorithm.
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<()> {
+        self.inner.setsockopt(libc::IPPROTO_TCP, libc::TCP_NODELAY, nodelay as libc::c_int)
+    }
+
+    pub fn nodelay(&self) -> Result<bool> {
+        let nodelay: libc::c_int = try!(self.inner.getsockopt(libc::IPPROTO_TCP,
+                                                              libc::TCP_NODELAY));
+        Ok(nodelay != 0)
+    }
+
+    /// Returns and clears the pending error on the socket, such as the outcome of `connect`.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+impl Read for TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        self.inner.recv_vectored(bufs)
+    }
+}
+
+impl Write for TcpStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        self.inner.send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Read for &'a TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        self.inner.recv_vectored(bufs)
+    }
+}
+
+impl<'a> Write for &'a TcpStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        self.inner.send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl FromRawFd for TcpStream {
+    /// Takes ownership of a connected socket, which must already be nonblocking.
+    unsafe fn from_raw_fd(fd: RawFd) -> TcpStream {
+        TcpStream { inner: Socket::from_raw_fd(fd) }
+    }
+}
+
+impl IntoRawFd for TcpStream {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
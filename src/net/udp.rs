@@ -0,0 +1,309 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::net::{SocketAddr, SocketAddrV4, Ipv4Addr};
+use std::io::{Result, Error};
+use std::cmp;
+
+use libc;
+
+use super::socket::{self, Socket};
+
+/// The size and source of a datagram received by `UdpSocket::recv_mmsg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecvMeta {
+    size: usize,
+    addr: SocketAddr,
+}
+
+impl RecvMeta {
+    /// The number of bytes received.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The address the datagram was sent from.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Default for RecvMeta {
+    fn default() -> RecvMeta {
+        RecvMeta {
+            size: 0,
+            addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 0)),
+        }
+    }
+}
+
+/// A nonblocking UDP socket.
+#[derive(Debug)]
+pub struct UdpSocket {
+    inner: Socket,
+}
+
+impl UdpSocket {
+    /// Creates a `UdpSocket` bound to `addr`.
+    pub fn bind(addr: &SocketAddr) -> Result<UdpSocket> {
+        let inner = try!(Socket::for_addr(addr, libc::SOCK_DGRAM));
+
+        let (storage, len) = socket::from_inet(addr);
+        try!(inner.bind(&storage, len));
+
+        Ok(UdpSocket { inner: inner })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        let (storage, _) = try!(self.inner.local_addr());
+        socket::to_inet(&storage)
+    }
+
+    /// Sends a datagram to `addr`, returning the number of bytes sent.
+    pub fn send_to(&self, buf: &[u8], addr: &SocketAddr) -> Result<usize> {
+        let (storage, len) = socket::from_inet(addr);
+        self.inner.send_to(buf, &storage, len)
+    }
+
+    /// Receives a datagram, returning the number of bytes read and the address it came from.
+    ///
+    /// Returns an error of kind `WouldBlock` if no datagram is queued.
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr)> {
+        let (n, storage, _) = try!(self.inner.recv_from(buf));
+        Ok((n, try!(socket::to_inet(&storage))))
+    }
+
+    /// Sets the default destination of `send`, and limits `recv` to datagrams from `addr`.
+    pub fn connect(&self, addr: &SocketAddr) -> Result<()> {
+        let (storage, len) = socket::from_inet(addr);
+        self.inner.connect(&storage, len)
+    }
+
+    /// Sends a datagram to the connected address.
+    pub fn send(&self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    /// Receives a datagram from the connected address.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    /// Sets `SO_BROADCAST`, allowing datagrams to be sent to a broadcast address.
+    pub fn set_broadcast(&self, broadcast: bool) -> Result<()> {
+        self.inner.setsockopt(libc::SOL_SOCKET, libc::SO_BROADCAST, broadcast as libc::c_int)
+    }
+
+    pub fn broadcast(&self) -> Result<bool> {
+        let broadcast: libc::c_int = try!(self.inner.getsockopt(libc::SOL_SOCKET,
+                                                                libc::SO_BROADCAST));
+        Ok(broadcast != 0)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+
+    /// Receives a batch of datagrams, one into each buffer of `bufs`, recording the size and
+    /// source of each in `meta`.
+    ///
+    /// Returns the number of datagrams received, which is at most the length of the shorter
+    /// slice. Datagrams longer than their buffer are truncated. Returns an error of kind
+    /// `WouldBlock` if no datagram is queued.
+    ///
+    /// On Linux this uses `recvmmsg(2)`, receiving many datagrams per system call.
+    pub fn recv_mmsg(&self, bufs: &mut [&mut [u8]], meta: &mut [RecvMeta]) -> Result<usize> {
+        let mut count = 0;
+
+        for (bufs, meta) in bufs.chunks_mut(mmsg::BATCH).zip(meta.chunks_mut(mmsg::BATCH)) {
+            match mmsg::recv(&self.inner, bufs, meta) {
+                Ok(n) => {
+                    count += n;
+                    if n < cmp::min(bufs.len(), meta.len()) {
+                        break;
+                    }
+                }
+                // Report the datagrams already received; the error will recur on the next call.
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Sends a batch of datagrams, each to its paired address.
+    ///
+    /// Returns the number of datagrams sent, which is less than `msgs.len()` if the socket's
+    /// send buffer fills. Returns an error of kind `WouldBlock` if none could be sent.
+    ///
+    /// On Linux this uses `sendmmsg(2)`, sending many datagrams per system call.
+    pub fn send_mmsg(&self, msgs: &[(&[u8], SocketAddr)]) -> Result<usize> {
+        let mut count = 0;
+
+        for msgs in msgs.chunks(mmsg::BATCH) {
+            match mmsg::send(&self.inner, msgs) {
+                Ok(n) => {
+                    count += n;
+                    if n < msgs.len() {
+                        break;
+                    }
+                }
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+impl AsRawFd for UdpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl FromRawFd for UdpSocket {
+    /// Takes ownership of a UDP socket, which must already be nonblocking.
+    unsafe fn from_raw_fd(fd: RawFd) -> UdpSocket {
+        UdpSocket { inner: Socket::from_raw_fd(fd) }
+    }
+}
+
+impl IntoRawFd for UdpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw_fd()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod mmsg {
+    use std::os::unix::io::AsRawFd;
+    use std::net::SocketAddr;
+    use std::io::Result;
+    use std::mem;
+    use std::ptr;
+
+    use libc;
+
+    use super::RecvMeta;
+    use net::socket::{self, Socket};
+
+    // The most datagrams handed to the kernel in one call, bounding the stack space used.
+    pub const BATCH: usize = 32;
+
+    pub fn recv(socket: &Socket, bufs: &mut [&mut [u8]], meta: &mut [RecvMeta]) -> Result<usize> {
+        let mut addrs: [libc::sockaddr_storage; BATCH] = unsafe { mem::zeroed() };
+        let mut iovecs: [libc::iovec; BATCH] = unsafe { mem::zeroed() };
+        let mut msgs: [libc::mmsghdr; BATCH] = unsafe { mem::zeroed() };
+
+        let mut n = 0;
+        for (((buf, addr), iovec), msg) in bufs.iter_mut()
+            .zip(addrs.iter_mut())
+            .zip(iovecs.iter_mut())
+            .zip(msgs.iter_mut())
+            .take(meta.len()) {
+            iovec.iov_base = buf.as_mut_ptr() as *mut libc::c_void;
+            iovec.iov_len = buf.len();
+            msg.msg_hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+            msg.msg_hdr.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            msg.msg_hdr.msg_iov = iovec;
+            msg.msg_hdr.msg_iovlen = 1;
+            n += 1;
+        }
+
+        let count = try!(socket::retry(|| unsafe {
+            libc::recvmmsg(socket.as_raw_fd(),
+                           msgs.as_mut_ptr(),
+                           n as libc::c_uint,
+                           0,
+                           ptr::null_mut())
+        })) as usize;
+
+        for ((meta, msg), addr) in meta.iter_mut().zip(&msgs).zip(&addrs).take(count) {
+            *meta = RecvMeta {
+                size: msg.msg_len as usize,
+                addr: try!(socket::to_inet(addr)),
+            };
+        }
+
+        Ok(count)
+    }
+
+    pub fn send(socket: &Socket, bufs: &[(&[u8], SocketAddr)]) -> Result<usize> {
+        let mut addrs: [libc::sockaddr_storage; BATCH] = unsafe { mem::zeroed() };
+        let mut iovecs: [libc::iovec; BATCH] = unsafe { mem::zeroed() };
+        let mut msgs: [libc::mmsghdr; BATCH] = unsafe { mem::zeroed() };
+
+        for (((&(buf, ref dst), addr), iovec), msg) in bufs.iter()
+            .zip(addrs.iter_mut())
+            .zip(iovecs.iter_mut())
+            .zip(msgs.iter_mut()) {
+            let (storage, len) = socket::from_inet(dst);
+            *addr = storage;
+
+            iovec.iov_base = buf.as_ptr() as *mut libc::c_void;
+            iovec.iov_len = buf.len();
+            msg.msg_hdr.msg_name = addr as *mut libc::sockaddr_storage as *mut libc::c_void;
+            msg.msg_hdr.msg_namelen = len;
+            msg.msg_hdr.msg_iov = iovec;
+            msg.msg_hdr.msg_iovlen = 1;
+        }
+
+        let count = try!(socket::retry(|| unsafe {
+            libc::sendmmsg(socket.as_raw_fd(),
+                           msgs.as_mut_ptr(),
+                           bufs.len() as libc::c_uint,
+                           libc::MSG_NOSIGNAL)
+        }));
+
+        Ok(count as usize)
+    }
+}
+
+// Elsewhere each datagram takes a system call of its own.
+#[cfg(not(target_os = "linux"))]
+mod mmsg {
+    use std::net::SocketAddr;
+    use std::io::Result;
+
+    use super::RecvMeta;
+    use net::socket::{self, Socket};
+
+    pub const BATCH: usize = 32;
+
+    pub fn recv(socket: &Socket, bufs: &mut [&mut [u8]], meta: &mut [RecvMeta]) -> Result<usize> {
+        let mut count = 0;
+
+        for (buf, meta) in bufs.iter_mut().zip(meta.iter_mut()) {
+            match socket.recv_from(buf) {
+                Ok((n, storage, _)) => {
+                    *meta = RecvMeta {
+                        size: n,
+                        addr: try!(socket::to_inet(&storage)),
+                    };
+                    count += 1;
+                }
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(count)
+    }
+
+    pub fn send(socket: &Socket, bufs: &[(&[u8], SocketAddr)]) -> Result<usize> {
+        let mut count = 0;
+
+        for &(buf, ref dst) in bufs {
+            let (storage, len) = socket::from_inet(dst);
+            match socket.send_to(buf, &storage, len) {
+                Ok(_) => count += 1,
+                Err(_) if count > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,538 @@
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::path::{Path, PathBuf};
+use std::net::Shutdown;
+use std::io::{Read, Write, IoSlice, IoSliceMut, Result, Error};
+
+use libc;
+
+use super::socket::{self, Socket};
+
+/// The address of a Unix domain socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnixAddr {
+    kind: AddrKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum AddrKind {
+    Unnamed,
+    Path(PathBuf),
+    Abstract(Vec<u8>),
+}
+
+impl UnixAddr {
+    /// An address in the filesystem.
+    pub fn path<P: AsRef<Path>>(path: P) -> UnixAddr {
+        UnixAddr { kind: AddrKind::Path(path.as_ref().to_path_buf()) }
+    }
+
+    /// An address in the Linux abstract namespace, which has no presence in the filesystem.
+    ///
+    /// Binding or connecting to an abstract address fails on other platforms.
+    pub fn abstract_name<N: AsRef<[u8]>>(name: N) -> UnixAddr {
+        UnixAddr { kind: AddrKind::Abstract(name.as_ref().to_vec()) }
+    }
+
+    /// The address of a socket that is not bound, such as either end of a `pair`.
+    pub fn unnamed() -> UnixAddr {
+        UnixAddr { kind: AddrKind::Unnamed }
+    }
+
+    pub fn as_path(&self) -> Option<&Path> {
+        match self.kind {
+            AddrKind::Path(ref path) => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn as_abstract_name(&self) -> Option<&[u8]> {
+        match self.kind {
+            AddrKind::Abstract(ref name) => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is_unnamed(&self) -> bool {
+        self.kind == AddrKind::Unnamed
+    }
+}
+
+/// The credentials of the process on the other end of a Unix socket connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UCred {
+    pid: Option<libc::pid_t>,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+impl UCred {
+    /// The peer's process id, where the platform reports it.
+    pub fn pid(&self) -> Option<libc::pid_t> {
+        self.pid
+    }
+
+    /// The peer's effective user id.
+    pub fn uid(&self) -> libc::uid_t {
+        self.uid
+    }
+
+    /// The peer's effective group id.
+    pub fn gid(&self) -> libc::gid_t {
+        self.gid
+    }
+}
+
+// Implements the raw file descriptor traits for a type wrapping a `Socket`.
+macro_rules! socket_fd {
+    ($($ty:ident),*) => {
+        $(
+            impl AsRawFd for $ty {
+                fn as_raw_fd(&self) -> RawFd {
+                    self.inner.as_raw_fd()
+                }
+            }
+
+            impl FromRawFd for $ty {
+                /// Takes ownership of a socket, which must already be nonblocking.
+                unsafe fn from_raw_fd(fd: RawFd) -> $ty {
+                    $ty { inner: Socket::from_raw_fd(fd) }
+                }
+            }
+
+            impl IntoRawFd for $ty {
+                fn into_raw_fd(self) -> RawFd {
+                    self.inner.into_raw_fd()
+                }
+            }
+        )*
+    }
+}
+
+fn bind(ty: libc::c_int, addr: &UnixAddr) -> Result<Socket> {
+    let inner = try!(Socket::new(libc::AF_UNIX, ty));
+
+    let (storage, len) = try!(socket::from_unix(addr));
+    try!(inner.bind(&storage, len));
+
+    Ok(inner)
+}
+
+fn connect(ty: libc::c_int, addr: &UnixAddr) -> Result<Socket> {
+    let inner = try!(Socket::new(libc::AF_UNIX, ty));
+
+    let (storage, len) = try!(socket::from_unix(addr));
+    try!(inner.connect(&storage, len));
+
+    Ok(inner)
+}
+
+fn local_addr(inner: &Socket) -> Result<UnixAddr> {
+    let (storage, len) = try!(inner.local_addr());
+    socket::to_unix(&storage, len)
+}
+
+fn peer_addr(inner: &Socket) -> Result<UnixAddr> {
+    let (storage, len) = try!(inner.peer_addr());
+    socket::to_unix(&storage, len)
+}
+
+// The credentials are those of the peer when it connected, or when the pair was created.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_cred(inner: &Socket) -> Result<UCred> {
+    let cred: libc::ucred = try!(inner.getsockopt(libc::SOL_SOCKET, libc::SO_PEERCRED));
+    Ok(UCred {
+        pid: Some(cred.pid),
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn peer_cred(inner: &Socket) -> Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    try!(::cvt(unsafe { libc::getpeereid(inner.as_raw_fd(), &mut uid, &mut gid) }));
+    Ok(UCred {
+        pid: None,
+        uid: uid,
+        gid: gid,
+    })
+}
+
+/// A nonblocking Unix domain stream socket.
+#[derive(Debug)]
+pub struct UnixStream {
+    inner: Socket,
+}
+
+impl UnixStream {
+    /// Connects to the socket bound at `path`.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<UnixStream> {
+        UnixStream::connect_addr(&UnixAddr::path(path))
+    }
+
+    /// Connects to the socket bound at `addr`.
+    ///
+    /// Returns an error of kind `WouldBlock` if the listener's backlog is full.
+    pub fn connect_addr(addr: &UnixAddr) -> Result<UnixStream> {
+        Ok(UnixStream { inner: try!(connect(libc::SOCK_STREAM, addr)) })
+    }
+
+    /// Creates a pair of connected, unnamed sockets.
+    pub fn pair() -> Result<(UnixStream, UnixStream)> {
+        let (a, b) = try!(Socket::pair(libc::AF_UNIX, libc::SOCK_STREAM));
+        Ok((UnixStream { inner: a }, UnixStream { inner: b }))
+    }
+
+    pub fn local_addr(&self) -> Result<UnixAddr> {
+        local_addr(&self.inner)
+    }
+
+    pub fn peer_addr(&self) -> Result<UnixAddr> {
+        peer_addr(&self.inner)
+    }
+
+    /// Returns the credentials of the peer process.
+    pub fn peer_cred(&self) -> Result<UCred> {
+        peer_cred(&self.inner)
+    }
+
+    /// Sends `buf` along with the file descriptors in `fds`, returning the number of bytes
+    /// sent. At least one byte must be sent with the descriptors.
+    ///
+    /// The descriptors are duplicated into the receiving process; the caller keeps its own.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> Result<usize> {
+        self.inner.send_with_fds(buf, fds)
+    }
+
+    /// Receives into `buf` along with up to `fds.len()` file descriptors, returning the number of
+    /// bytes and descriptors received.
+    ///
+    /// The caller owns the received descriptors, which are nonblocking and close-on-exec. Any
+    /// sent beyond the space in `fds` are closed.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd]) -> Result<(usize, usize)> {
+        self.inner.recv_with_fds(buf, fds)
+    }
+
+    /// Shuts down the read half, write half or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        self.inner.shutdown(how)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+impl Read for UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        self.inner.recv_vectored(bufs)
+    }
+}
+
+impl Write for UnixStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        self.inner.send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Read for &'a UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        self.inner.recv_vectored(bufs)
+    }
+}
+
+impl<'a> Write for &'a UnixStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        self.inner.send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A nonblocking Unix domain socket listening for stream connections.
+#[derive(Debug)]
+pub struct UnixListener {
+    inner: Socket,
+}
+
+impl UnixListener {
+    /// Creates a `UnixListener` bound to `path`, which must not already exist.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<UnixListener> {
+        UnixListener::bind_addr(&UnixAddr::path(path))
+    }
+
+    /// Creates a `UnixListener` bound to `addr`.
+    pub fn bind_addr(addr: &UnixAddr) -> Result<UnixListener> {
+        let inner = try!(bind(libc::SOCK_STREAM, addr));
+        try!(inner.listen(1024));
+
+        Ok(UnixListener { inner: inner })
+    }
+
+    /// Accepts a pending connection.
+    ///
+    /// Returns an error of kind `WouldBlock` if there are none.
+    pub fn accept(&self) -> Result<(UnixStream, UnixAddr)> {
+        let (inner, storage, len) = try!(self.inner.accept());
+        let addr = try!(socket::to_unix(&storage, len));
+
+        Ok((UnixStream { inner: inner }, addr))
+    }
+
+    pub fn local_addr(&self) -> Result<UnixAddr> {
+        local_addr(&self.inner)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+/// A nonblocking Unix domain datagram socket.
+#[derive(Debug)]
+pub struct UnixDatagram {
+    inner: Socket,
+}
+
+impl UnixDatagram {
+    /// Creates a `UnixDatagram` bound to `path`, which must not already exist.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<UnixDatagram> {
+        UnixDatagram::bind_addr(&UnixAddr::path(path))
+    }
+
+    /// Creates a `UnixDatagram` bound to `addr`.
+    pub fn bind_addr(addr: &UnixAddr) -> Result<UnixDatagram> {
+        Ok(UnixDatagram { inner: try!(bind(libc::SOCK_DGRAM, addr)) })
+    }
+
+    /// Creates a `UnixDatagram` that is not bound to any address.
+    pub fn unbound() -> Result<UnixDatagram> {
+        Ok(UnixDatagram { inner: try!(Socket::new(libc::AF_UNIX, libc::SOCK_DGRAM)) })
+    }
+
+    /// Creates a pair of connected, unnamed sockets.
+    pub fn pair() -> Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = try!(Socket::pair(libc::AF_UNIX, libc::SOCK_DGRAM));
+        Ok((UnixDatagram { inner: a }, UnixDatagram { inner: b }))
+    }
+
+    /// Sets the default destination of `send`, and limits `recv` to datagrams from `path`.
+    pub fn connect<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.connect_addr(&UnixAddr::path(path))
+    }
+
+    /// Sets the default destination of `send`, and limits `recv` to datagrams from `addr`.
+    pub fn connect_addr(&self, addr: &UnixAddr) -> Result<()> {
+        let (storage, len) = try!(socket::from_unix(addr));
+        self.inner.connect(&storage, len)
+    }
+
+    /// Sends a datagram to the socket bound at `path`.
+    pub fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P) -> Result<usize> {
+        self.send_to_addr(buf, &UnixAddr::path(path))
+    }
+
+    /// Sends a datagram to the socket bound at `addr`.
+    pub fn send_to_addr(&self, buf: &[u8], addr: &UnixAddr) -> Result<usize> {
+        let (storage, len) = try!(socket::from_unix(addr));
+        self.inner.send_to(buf, &storage, len)
+    }
+
+    /// Receives a datagram, returning the number of bytes read and the address it came from.
+    ///
+    /// Returns an error of kind `WouldBlock` if no datagram is queued.
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, UnixAddr)> {
+        let (n, storage, len) = try!(self.inner.recv_from(buf));
+        Ok((n, try!(socket::to_unix(&storage, len))))
+    }
+
+    /// Sends a datagram to the connected address.
+    pub fn send(&self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    /// Receives a datagram from the connected address.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    pub fn local_addr(&self) -> Result<UnixAddr> {
+        local_addr(&self.inner)
+    }
+
+    pub fn peer_addr(&self) -> Result<UnixAddr> {
+        peer_addr(&self.inner)
+    }
+
+    /// Sends a datagram to the connected address along with the file descriptors in `fds`,
+    /// returning the number of bytes sent.
+    ///
+    /// The descriptors are duplicated into the receiving process; the caller keeps its own.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> Result<usize> {
+        self.inner.send_with_fds(buf, fds)
+    }
+
+    /// Receives into `buf` along with up to `fds.len()` file descriptors, returning the number of
+    /// bytes and descriptors received.
+    ///
+    /// The caller owns the received descriptors, which are nonblocking and close-on-exec. Any
+    /// sent beyond the space in `fds` are closed.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd]) -> Result<(usize, usize)> {
+        self.inner.recv_with_fds(buf, fds)
+    }
+
+    /// Shuts down the read half, write half or both halves of the socket.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        self.inner.shutdown(how)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+/// A nonblocking Unix domain sequenced-packet socket.
+///
+/// Like a stream, it is connection-oriented and reliable, but each `send` is delivered to the
+/// peer as a single message, as with a datagram socket.
+#[derive(Debug)]
+pub struct UnixSeqpacket {
+    inner: Socket,
+}
+
+impl UnixSeqpacket {
+    /// Connects to the socket bound at `path`.
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<UnixSeqpacket> {
+        UnixSeqpacket::connect_addr(&UnixAddr::path(path))
+    }
+
+    /// Connects to the socket bound at `addr`.
+    pub fn connect_addr(addr: &UnixAddr) -> Result<UnixSeqpacket> {
+        Ok(UnixSeqpacket { inner: try!(connect(libc::SOCK_SEQPACKET, addr)) })
+    }
+
+    /// Creates a pair of connected, unnamed sockets.
+    pub fn pair() -> Result<(UnixSeqpacket, UnixSeqpacket)> {
+        let (a, b) = try!(Socket::pair(libc::AF_UNIX, libc::SOCK_SEQPACKET));
+        Ok((UnixSeqpacket { inner: a }, UnixSeqpacket { inner: b }))
+    }
+
+    /// Sends a message to the peer.
+    pub fn send(&self, buf: &[u8]) -> Result<usize> {
+        self.inner.send(buf)
+    }
+
+    /// Receives a message from the peer. Any part of the message that does not fit in `buf` is
+    /// discarded.
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        self.inner.recv(buf)
+    }
+
+    pub fn local_addr(&self) -> Result<UnixAddr> {
+        local_addr(&self.inner)
+    }
+
+    pub fn peer_addr(&self) -> Result<UnixAddr> {
+        peer_addr(&self.inner)
+    }
+
+    /// Returns the credentials of the peer process.
+    pub fn peer_cred(&self) -> Result<UCred> {
+        peer_cred(&self.inner)
+    }
+
+    /// Sends a message to the peer along with the file descriptors in `fds`, returning the
+    /// number of bytes sent.
+    ///
+    /// The descriptors are duplicated into the receiving process; the caller keeps its own.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> Result<usize> {
+        self.inner.send_with_fds(buf, fds)
+    }
+
+    /// Receives into `buf` along with up to `fds.len()` file descriptors, returning the number of
+    /// bytes and descriptors received.
+    ///
+    /// The caller owns the received descriptors, which are nonblocking and close-on-exec. Any
+    /// sent beyond the space in `fds` are closed.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd]) -> Result<(usize, usize)> {
+        self.inner.recv_with_fds(buf, fds)
+    }
+
+    /// Shuts down the read half, write half or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> Result<()> {
+        self.inner.shutdown(how)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+/// A nonblocking Unix domain socket listening for sequenced-packet connections.
+#[derive(Debug)]
+pub struct UnixSeqpacketListener {
+    inner: Socket,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a `UnixSeqpacketListener` bound to `path`, which must not already exist.
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<UnixSeqpacketListener> {
+        UnixSeqpacketListener::bind_addr(&UnixAddr::path(path))
+    }
+
+    /// Creates a `UnixSeqpacketListener` bound to `addr`.
+    pub fn bind_addr(addr: &UnixAddr) -> Result<UnixSeqpacketListener> {
+        let inner = try!(bind(libc::SOCK_SEQPACKET, addr));
+        try!(inner.listen(1024));
+
+        Ok(UnixSeqpacketListener { inner: inner })
+    }
+
+    /// Accepts a pending connection.
+    ///
+    /// Returns an error of kind `WouldBlock` if there are none.
+    pub fn accept(&self) -> Result<(UnixSeqpacket, UnixAddr)> {
+        let (inner, storage, len) = try!(self.inner.accept());
+        let addr = try!(socket::to_unix(&storage, len));
+
+        Ok((UnixSeqpacket { inner: inner }, addr))
+    }
+
+    pub fn local_addr(&self) -> Result<UnixAddr> {
+        local_addr(&self.inner)
+    }
+
+    /// Returns and clears the pending error on the socket.
+    pub fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+}
+
+socket_fd!(UnixStream, UnixListener, UnixDatagram, UnixSeqpacket, UnixSeqpacketListener);
@@ -0,0 +1,281 @@
+//! Nonblocking anonymous pipes.
+//!
+//! Both ends of a `Pipe` are nonblocking and close-on-exec, and can be registered with a
+//! `Selector`. Reads from an empty pipe and writes to a full one fail with `ErrorKind::WouldBlock`.
+
+use std::os::unix::io::{RawFd, AsRawFd, FromRawFd, IntoRawFd};
+use std::io::{Read, Write, IoSlice, IoSliceMut, Result, Error};
+use std::mem;
+use std::cmp;
+
+use libc;
+
+/// A unidirectional channel, with a `Sender` that writes bytes for its `Receiver` to read.
+#[derive(Debug)]
+pub struct Pipe {
+    sender: Sender,
+    receiver: Receiver,
+}
+
+impl Pipe {
+    #[cfg(any(target_os = "linux",
+              target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly"))]
+    pub fn new() -> Result<Pipe> {
+        let mut fds = [0 as libc::c_int; 2];
+        let res = unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) };
+
+        if res == -1 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(Pipe {
+                sender: Sender { fd: fds[1] },
+                receiver: Receiver { fd: fds[0] },
+            })
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux",
+                  target_os = "freebsd",
+                  target_os = "openbsd",
+                  target_os = "netbsd",
+                  target_os = "bitrig",
+                  target_os = "dragonfly")))]
+    pub fn new() -> Result<Pipe> {
+        let mut fds = [0 as libc::c_int; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            return Err(Error::last_os_error());
+        }
+
+        let pipe = Pipe {
+            sender: Sender { fd: fds[1] },
+            receiver: Receiver { fd: fds[0] },
+        };
+        for &fd in &fds {
+            try!(unsafe { ::set_nonblock(fd) });
+            try!(unsafe { ::set_cloexec(fd) });
+        }
+
+        Ok(pipe)
+    }
+
+    pub fn sender(&self) -> &Sender {
+        &self.sender
+    }
+
+    pub fn receiver(&self) -> &Receiver {
+        &self.receiver
+    }
+
+    /// Separates the pipe into its two ends.
+    pub fn split(self) -> (Sender, Receiver) {
+        (self.sender, self.receiver)
+    }
+
+    /// Sets the size of the pipe's buffer to at least `size` bytes, returning the size actually
+    /// used.
+    #[cfg(target_os = "linux")]
+    pub fn set_capacity(&self, size: usize) -> Result<usize> {
+        set_capacity(self.sender.fd, size)
+    }
+
+    /// Returns the size of the pipe's buffer.
+    #[cfg(target_os = "linux")]
+    pub fn capacity(&self) -> Result<usize> {
+        capacity(self.sender.fd)
+    }
+}
+
+/// The write end of a `Pipe`.
+///
+/// Once the `Receiver` is dropped, writes fail with `ErrorKind::BrokenPipe`, provided `SIGPIPE`
+/// is ignored.
+#[derive(Debug)]
+pub struct Sender {
+    fd: RawFd,
+}
+
+/// The read end of a `Pipe`.
+///
+/// Once the `Sender` is dropped and the pipe is drained, reads return `Ok(0)`.
+#[derive(Debug)]
+pub struct Receiver {
+    fd: RawFd,
+}
+
+fn read(fd: RawFd, buf: &mut [u8]) -> Result<usize> {
+    let res = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+fn write(fd: RawFd, buf: &[u8]) -> Result<usize> {
+    let res = unsafe { libc::write(fd, buf.as_ptr() as *const libc::c_void, buf.len()) };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+fn read_vectored(fd: RawFd, bufs: &mut [IoSliceMut]) -> Result<usize> {
+    let res = unsafe {
+        libc::readv(fd,
+                    bufs.as_ptr() as *const libc::iovec,
+                    cmp::min(bufs.len(), ::IOV_MAX) as libc::c_int)
+    };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+fn write_vectored(fd: RawFd, bufs: &[IoSlice]) -> Result<usize> {
+    let res = unsafe {
+        libc::writev(fd,
+                     bufs.as_ptr() as *const libc::iovec,
+                     cmp::min(bufs.len(), ::IOV_MAX) as libc::c_int)
+    };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_capacity(fd: RawFd, size: usize) -> Result<usize> {
+    let size = if size > libc::c_int::max_value() as usize {
+        libc::c_int::max_value()
+    } else {
+        size as libc::c_int
+    };
+    let res = unsafe { libc::fcntl(fd, libc::F_SETPIPE_SZ, size) };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn capacity(fd: RawFd) -> Result<usize> {
+    let res = unsafe { libc::fcntl(fd, libc::F_GETPIPE_SZ) };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+impl Read for Receiver {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        read(self.fd, buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        read_vectored(self.fd, bufs)
+    }
+}
+
+impl<'a> Read for &'a Receiver {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        read(self.fd, buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        read_vectored(self.fd, bufs)
+    }
+}
+
+impl Write for Sender {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        write(self.fd, buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        write_vectored(self.fd, bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Write for &'a Sender {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        write(self.fd, buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        write_vectored(self.fd, bufs)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Implements capacity and ownership of the file descriptor for each end of the pipe.
+macro_rules! pipe_end {
+    ($($ty:ident),*) => {
+        $(
+            impl $ty {
+                /// Sets the size of the pipe's buffer to at least `size` bytes, returning the
+                /// size actually used.
+                #[cfg(target_os = "linux")]
+                pub fn set_capacity(&self, size: usize) -> Result<usize> {
+                    set_capacity(self.fd, size)
+                }
+
+                /// Returns the size of the pipe's buffer.
+                #[cfg(target_os = "linux")]
+                pub fn capacity(&self) -> Result<usize> {
+                    capacity(self.fd)
+                }
+            }
+
+            impl AsRawFd for $ty {
+                fn as_raw_fd(&self) -> RawFd {
+                    self.fd
+                }
+            }
+
+            impl FromRawFd for $ty {
+                /// Takes ownership of one end of a pipe, which must already be nonblocking.
+                unsafe fn from_raw_fd(fd: RawFd) -> $ty {
+                    $ty { fd: fd }
+                }
+            }
+
+            impl IntoRawFd for $ty {
+                fn into_raw_fd(self) -> RawFd {
+                    let fd = self.fd;
+                    mem::forget(self);
+                    fd
+                }
+            }
+
+            impl Drop for $ty {
+                fn drop(&mut self) {
+                    let _ = unsafe { libc::close(self.fd) };
+                }
+            }
+        )*
+    }
+}
+
+pipe_end!(Sender, Receiver);
@@ -0,0 +1,436 @@
+use std::fmt;
+use std::io::{Result, Error, ErrorKind};
+use std::mem;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use std::usize;
+
+use event::{EventSet, PollOpt};
+use event_loop::{EventLoop, Handler, Sender};
+use net::{TcpListener, TcpStream};
+use selector::Fired;
+use token::Token;
+use waker::Waker;
+
+// The token under which each thread registers its listener, and schedules its re-registration.
+const LISTENER: Token = Token(usize::MAX - 1);
+
+// How long a thread stops listening after accepting fails with an error other than `WouldBlock`.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A `Handler` run on each thread of a `ThreadPoolLoop`, which is given the connections assigned
+/// to its thread.
+pub trait PoolHandler: Handler {
+    /// Called on the thread a connection is assigned to, once it has been accepted.
+    ///
+    /// The connection counts toward the thread's load until `Worker::release` is called.
+    fn accept(&mut self, event_loop: &mut EventLoop<Self>, stream: TcpStream, addr: SocketAddr);
+}
+
+/// How a `ThreadPoolLoop` spreads incoming connections over its threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    /// Each thread listens on a socket of its own bound with `SO_REUSEPORT`, and the kernel
+    /// spreads connections between them. Connections stay on the thread that accepted them.
+    ReusePort,
+    /// The threads share one listening socket, and accepted connections are handed to each
+    /// thread in turn.
+    RoundRobin,
+    /// The threads share one listening socket, and accepted connections are handed to the thread
+    /// with the least load.
+    LeastLoaded,
+}
+
+/// Runs an `EventLoop` on each of several threads, spreading the connections to a TCP address
+/// between them.
+///
+/// Each thread has a `Selector` of its own, so the threads do not contend on a single poll. A
+/// shared listening socket is registered with every thread's `Selector` using
+/// `PollOpt::exclusive`, so that a connection wakes a single thread, which accepts it and hands
+/// it to the thread chosen by the `Distribution` through that thread's `Waker`.
+///
+/// The pool registers its listener on each thread under `Token(usize::MAX - 1)`, which must not
+/// be used for other registrations or timeouts.
+pub struct ThreadPoolLoop<H: Handler> {
+    pool: Arc<Pool>,
+    senders: Vec<Sender<H::Message>>,
+    threads: Vec<thread::JoinHandle<Result<()>>>,
+    local_addr: SocketAddr,
+}
+
+// The state shared by the threads of a pool.
+struct Pool {
+    workers: Vec<Arc<Shared>>,
+    distribution: Distribution,
+    // The next thread to be handed a connection under round-robin distribution.
+    next: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Shared {
+    waker: Waker,
+    // Connections handed to the thread and not yet passed to its handler.
+    queue: Mutex<Vec<(TcpStream, SocketAddr)>>,
+    load: AtomicUsize,
+    // Set to ask the thread to stop.
+    shutdown: AtomicBool,
+    // Set once the thread's loop has stopped, after which it is handed no connections. Only set
+    // while holding the `queue` lock.
+    stopped: AtomicBool,
+}
+
+/// A thread of a `ThreadPoolLoop`, given to the function creating its `PoolHandler`.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    index: usize,
+    shared: Arc<Shared>,
+}
+
+impl<H> ThreadPoolLoop<H>
+    where H: PoolHandler + 'static,
+          H::Message: 'static
+{
+    /// Listens on `addr` with `threads` threads, each running a `PoolHandler` created by
+    /// `factory` on that thread.
+    pub fn bind<F>(addr: &SocketAddr,
+                   threads: usize,
+                   distribution: Distribution,
+                   factory: F)
+                   -> Result<ThreadPoolLoop<H>>
+        where F: Fn(Worker) -> H + Send + Sync + 'static
+    {
+        if threads == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "a pool needs at least one thread"));
+        }
+
+        // Once the first listener is bound, the rest bind its address, in case `addr` asked for
+        // any port.
+        let mut listeners = Vec::new();
+        let first = if distribution == Distribution::ReusePort {
+            try!(TcpListener::bind_reuseport(addr))
+        } else {
+            try!(TcpListener::bind(addr))
+        };
+        let local_addr = try!(first.local_addr());
+        listeners.push(Arc::new(first));
+        for _ in 1..threads {
+            if distribution == Distribution::ReusePort {
+                listeners.push(Arc::new(try!(TcpListener::bind_reuseport(&local_addr))));
+            } else {
+                let shared = listeners[0].clone();
+                listeners.push(shared);
+            }
+        }
+
+        let mut event_loops = Vec::new();
+        let mut workers = Vec::new();
+        for _ in 0..threads {
+            let mut event_loop = try!(EventLoop::<H>::new());
+            workers.push(Arc::new(Shared {
+                waker: try!(Waker::new(event_loop.selector())),
+                queue: Mutex::new(Vec::new()),
+                load: AtomicUsize::new(0),
+                shutdown: AtomicBool::new(false),
+                stopped: AtomicBool::new(false),
+            }));
+            event_loops.push(event_loop);
+        }
+
+        for (event_loop, listener) in event_loops.iter_mut().zip(&listeners) {
+            try!(listen(event_loop, listener, distribution));
+        }
+
+        let pool = Arc::new(Pool {
+            workers: workers,
+            distribution: distribution,
+            next: AtomicUsize::new(0),
+        });
+        let factory = Arc::new(factory);
+        let senders = event_loops.iter().map(EventLoop::channel).collect();
+
+        let mut handles = Vec::new();
+        for (index, (event_loop, listener)) in event_loops.into_iter().zip(listeners).enumerate() {
+            let thread_pool = pool.clone();
+            let factory = factory.clone();
+
+            let res = thread::Builder::new()
+                .name(format!("rivet-pool-{}", index))
+                .spawn(move || run(event_loop, &*factory, &thread_pool, index, &listener));
+            match res {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    for worker in &pool.workers {
+                        worker.stop();
+                    }
+                    for handle in handles {
+                        let _ = handle.join();
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(ThreadPoolLoop {
+            pool: pool,
+            senders: senders,
+            threads: handles,
+            local_addr: local_addr,
+        })
+    }
+}
+
+impl<H: Handler> ThreadPoolLoop<H> {
+    /// Returns the address the pool is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the number of threads in the pool.
+    pub fn threads(&self) -> usize {
+        self.pool.workers.len()
+    }
+
+    /// Returns the number of connections assigned to thread `index` and not yet released.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than the number of threads.
+    pub fn load(&self, index: usize) -> usize {
+        self.pool.workers[index].load.load(Ordering::SeqCst)
+    }
+
+    /// Returns a `Sender` for delivering messages to the handler of thread `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than the number of threads.
+    pub fn sender(&self, index: usize) -> Sender<H::Message> {
+        self.senders[index].clone()
+    }
+
+    /// Asks every thread to stop once its current iteration completes.
+    pub fn shutdown(&self) {
+        for worker in &self.pool.workers {
+            worker.stop();
+        }
+    }
+
+    /// Waits for every thread to stop, returning the first error with which one stopped.
+    pub fn join(mut self) -> Result<()> {
+        self.join_threads()
+    }
+
+    fn join_threads(&mut self) -> Result<()> {
+        let mut res = Ok(());
+
+        for handle in self.threads.drain(..) {
+            let thread_res = match handle.join() {
+                Ok(thread_res) => thread_res,
+                Err(_) => Err(Error::new(ErrorKind::Other, "pool thread panicked")),
+            };
+            if res.is_ok() {
+                res = thread_res;
+            }
+        }
+
+        res
+    }
+}
+
+impl<H: Handler> Drop for ThreadPoolLoop<H> {
+    fn drop(&mut self) {
+        self.shutdown();
+        let _ = self.join_threads();
+    }
+}
+
+impl<H: Handler> fmt::Debug for ThreadPoolLoop<H> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ThreadPoolLoop")
+            .field("local_addr", &self.local_addr)
+            .field("threads", &self.pool.workers.len())
+            .field("distribution", &self.pool.distribution)
+            .finish()
+    }
+}
+
+impl Worker {
+    /// Returns the index of the thread within its pool.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the number of connections assigned to the thread and not yet released.
+    pub fn load(&self) -> usize {
+        self.shared.load.load(Ordering::SeqCst)
+    }
+
+    /// Releases a connection given to `PoolHandler::accept`, once it has been closed.
+    pub fn release(&self) {
+        self.shared.release();
+    }
+}
+
+impl Shared {
+    fn stop(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _ = self.waker.wake();
+    }
+
+    fn release(&self) {
+        let _ = self.load.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |load| {
+            load.checked_sub(1)
+        });
+    }
+
+    // Queues a connection for the thread and wakes it, or gives the connection back if the
+    // thread's loop has stopped.
+    fn hand_off(&self, stream: TcpStream, addr: SocketAddr) -> Option<(TcpStream, SocketAddr)> {
+        let mut queue = self.queue.lock().unwrap();
+        if self.stopped.load(Ordering::SeqCst) {
+            return Some((stream, addr));
+        }
+        queue.push((stream, addr));
+        drop(queue);
+
+        let _ = self.waker.wake();
+        None
+    }
+}
+
+impl Pool {
+    // Chooses the thread to be given a connection accepted on thread `index`, skipping threads
+    // that have stopped.
+    fn assign(&self, index: usize) -> usize {
+        let running = |&i: &usize| !self.workers[i].stopped.load(Ordering::SeqCst);
+        let n = self.workers.len();
+
+        let target = match self.distribution {
+            Distribution::ReusePort => None,
+            Distribution::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed);
+                (0..n).map(|i| (start + i) % n).find(running)
+            }
+            Distribution::LeastLoaded => {
+                (0..n).filter(running).min_by_key(|&i| self.workers[i].load.load(Ordering::SeqCst))
+            }
+        };
+
+        let target = target.unwrap_or(index);
+        self.workers[target].load.fetch_add(1, Ordering::SeqCst);
+        target
+    }
+}
+
+// Runs the event loop of thread `index` until it is shut down.
+fn run<H, F>(mut event_loop: EventLoop<H>,
+             factory: &F,
+             pool: &Pool,
+             index: usize,
+             listener: &TcpListener)
+             -> Result<()>
+    where H: PoolHandler,
+          F: Fn(Worker) -> H
+{
+    let shared = &pool.workers[index];
+    let mut handler = factory(Worker {
+        index: index,
+        shared: shared.clone(),
+    });
+
+    let res = event_loop.run_with(&mut handler, |event_loop, handler, fired| {
+        intercept(event_loop, handler, fired, pool, index, listener)
+    });
+
+    // Connections handed off before the thread stopped are dropped, and no more can be queued.
+    let mut queue = shared.queue.lock().unwrap();
+    shared.stopped.store(true, Ordering::SeqCst);
+    for _ in queue.drain(..) {
+        shared.release();
+    }
+    drop(queue);
+
+    res
+}
+
+// Accepts connections when the listener is readable, and passes handed-off connections to the
+// handler on a wakeup.
+fn intercept<H: PoolHandler>(event_loop: &mut EventLoop<H>,
+                             handler: &mut H,
+                             fired: &Fired,
+                             pool: &Pool,
+                             index: usize,
+                             listener: &TcpListener)
+                             -> bool {
+    let shared = &pool.workers[index];
+
+    if fired.is_wakeup() {
+        if shared.shutdown.load(Ordering::SeqCst) {
+            event_loop.shutdown();
+        }
+
+        let handed = mem::replace(&mut *shared.queue.lock().unwrap(), Vec::new());
+        for (stream, addr) in handed {
+            handler.accept(event_loop, stream, addr);
+        }
+        return false;
+    }
+
+    if fired.token() != LISTENER {
+        return false;
+    }
+
+    // The backoff after a failed accept has elapsed.
+    if fired.is_timeout() {
+        if listen(event_loop, listener, pool.distribution).is_err() {
+            event_loop.timeout(LISTENER, ACCEPT_BACKOFF);
+        }
+        return true;
+    }
+
+    loop {
+        let (stream, addr) = match listener.accept() {
+            Ok(conn) => conn,
+            // Another thread may have taken the connection.
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(ref err) if err.kind() == ErrorKind::ConnectionAborted ||
+                            err.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => {
+                // An error such as running out of file descriptors persists, and would keep the
+                // level-triggered listener ready, so the thread stops listening for a while.
+                let _ = event_loop.deregister(listener);
+                event_loop.timeout(LISTENER, ACCEPT_BACKOFF);
+                break;
+            }
+        };
+
+        let target = pool.assign(index);
+        if target == index {
+            handler.accept(event_loop, stream, addr);
+        } else if let Some((stream, addr)) = pool.workers[target].hand_off(stream, addr) {
+            // The target stopped after being chosen, so the connection is kept here instead.
+            pool.workers[target].release();
+            shared.load.fetch_add(1, Ordering::SeqCst);
+            handler.accept(event_loop, stream, addr);
+        }
+    }
+
+    true
+}
+
+// Registers a thread's listener with its event loop.
+fn listen<H: Handler>(event_loop: &mut EventLoop<H>,
+                      listener: &TcpListener,
+                      distribution: Distribution)
+                      -> Result<()> {
+    let opts = if distribution == Distribution::ReusePort {
+        PollOpt::level()
+    } else {
+        PollOpt::level() | PollOpt::exclusive()
+    };
+
+    event_loop.register(listener, LISTENER, EventSet::readable(), opts)
+}
@@ -0,0 +1,517 @@
+use std::fmt;
+use std::io::{Read, Write, Result, ErrorKind};
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{self, Context, Poll};
+use std::time::{Duration, Instant};
+
+use event::{EventSet, PollOpt};
+use selector::Selector;
+use token::Token;
+use waker::Waker;
+use wheel::Timeout;
+
+/// Delivers readiness from a `Selector` to the tasks of `std::future` code.
+///
+/// Sources registered with a `Reactor` yield a `Registration`, which stores the `task::Waker` of
+/// the task waiting on each direction. Each call to `turn` polls the `Selector` and wakes the
+/// tasks whose sources became ready.
+///
+/// A `Reactor` is a handle; clones share the same `Selector`. Sources may be registered from any
+/// thread, including while another is blocked in `turn`.
+#[derive(Clone)]
+pub struct Reactor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    selector: Mutex<Selector>,
+    sources: Mutex<Slab<Source>>,
+    timers: Mutex<Slab<Mutex<TimerState>>>,
+    waker: Waker,
+    // Threads that have woken a blocked `turn` and are waiting for the selector. The wakeup may
+    // be consumed before they get the lock, so `turn` does not block while any are waiting.
+    waiting: AtomicUsize,
+}
+
+// Registered sources or timers, indexed by their tokens.
+struct Slab<T> {
+    entries: Vec<Option<Arc<T>>>,
+    free: Vec<usize>,
+}
+
+struct Source {
+    fd: RawFd,
+    state: Mutex<State>,
+}
+
+struct State {
+    // The directions reported ready and not since cleared.
+    ready: EventSet,
+    // The directions the oneshot registration is armed for, empty once it has fired.
+    armed: EventSet,
+    reader: Option<task::Waker>,
+    writer: Option<task::Waker>,
+}
+
+impl Reactor {
+    /// Creates a `Reactor` using a `Selector` with the default backend.
+    pub fn new() -> Result<Reactor> {
+        Reactor::with_selector(try!(Selector::new()))
+    }
+
+    /// Creates a `Reactor` driven by the given `Selector`.
+    pub fn with_selector(mut selector: Selector) -> Result<Reactor> {
+        let waker = try!(Waker::new(&mut selector));
+
+        Ok(Reactor {
+            inner: Arc::new(Inner {
+                selector: Mutex::new(selector),
+                sources: Mutex::new(Slab::new()),
+                timers: Mutex::new(Slab::new()),
+                waker: waker,
+                waiting: AtomicUsize::new(0),
+            }),
+        })
+    }
+
+    /// Registers `io` for readiness in both directions.
+    ///
+    /// Both directions start out ready, so the first attempt at I/O is made without waiting. The
+    /// returned `Registration` deregisters `io` when dropped, which must happen before `io` is
+    /// closed.
+    pub fn register<E: ?Sized + AsRawFd>(&self, io: &E) -> Result<Registration> {
+        let source = Arc::new(Source {
+            fd: io.as_raw_fd(),
+            state: Mutex::new(State {
+                ready: EventSet::readable() | EventSet::writable(),
+                armed: EventSet::empty(),
+                reader: None,
+                writer: None,
+            }),
+        });
+
+        let token = self.inner.sources.lock().unwrap().insert(source.clone());
+        let res = self.selector().register(source.fd, token, EventSet::empty(), PollOpt::oneshot());
+        if let Err(err) = res {
+            self.inner.sources.lock().unwrap().remove(token);
+            return Err(err);
+        }
+
+        Ok(Registration {
+            reactor: self.clone(),
+            token: token,
+            source: source,
+        })
+    }
+
+    /// Polls the `Selector` once, waking the tasks waiting on each source that became ready or
+    /// timer that expired, and returns the number of tasks woken.
+    ///
+    /// Blocks until an event fires, `timeout` elapses or `wake` is called, and no longer than the
+    /// nearest timer.
+    pub fn turn(&self, timeout: Option<Duration>) -> Result<usize> {
+        let events = {
+            let mut selector = self.inner.selector.lock().unwrap();
+            let timeout = if self.inner.waiting.load(Ordering::SeqCst) > 0 {
+                Some(Duration::from_secs(0))
+            } else {
+                timeout
+            };
+            match selector.poll(timeout) {
+                Ok(fired) => {
+                    fired.filter(|fired| !fired.is_wakeup())
+                        .map(|fired| (fired.token(), fired.evset(), fired.is_timeout()))
+                        .collect::<Vec<_>>()
+                }
+                Err(err) => return Err(err),
+            }
+        };
+
+        let mut wakers = Vec::new();
+        {
+            let sources = self.inner.sources.lock().unwrap();
+            let timers = self.inner.timers.lock().unwrap();
+            for (token, evset, is_timeout) in events {
+                if is_timeout {
+                    if let Some(timer) = timers.get(token) {
+                        let mut state = timer.lock().unwrap();
+                        state.fired = true;
+                        wakers.extend(state.waker.take());
+                    }
+                } else if let Some(source) = sources.get(token) {
+                    source.fire(evset, &mut wakers);
+                }
+            }
+        }
+
+        let woken = wakers.len();
+        for waker in wakers {
+            waker.wake();
+        }
+
+        Ok(woken)
+    }
+
+    /// Wakes a thread blocked in `turn`.
+    pub fn wake(&self) -> Result<()> {
+        self.inner.waker.wake()
+    }
+
+    // Locks the selector, first waking any thread blocked polling it so that it gives up the lock.
+    fn selector(&self) -> MutexGuard<Selector> {
+        match self.inner.selector.try_lock() {
+            Ok(selector) => selector,
+            Err(TryLockError::WouldBlock) => {
+                self.inner.waiting.fetch_add(1, Ordering::SeqCst);
+                let _ = self.inner.waker.wake();
+                let selector = self.inner.selector.lock();
+                self.inner.waiting.fetch_sub(1, Ordering::SeqCst);
+                selector.unwrap()
+            }
+            Err(TryLockError::Poisoned(err)) => panic!("{}", err),
+        }
+    }
+}
+
+impl fmt::Debug for Reactor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Reactor")
+            .field("waker", &self.inner.waker)
+            .finish()
+    }
+}
+
+impl<T> Slab<T> {
+    fn new() -> Slab<T> {
+        Slab {
+            entries: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, entry: Arc<T>) -> Token {
+        match self.free.pop() {
+            Some(index) => {
+                self.entries[index] = Some(entry);
+                Token(index)
+            }
+            None => {
+                self.entries.push(Some(entry));
+                Token(self.entries.len() - 1)
+            }
+        }
+    }
+
+    fn get(&self, token: Token) -> Option<&Arc<T>> {
+        self.entries.get(token.0).and_then(|entry| entry.as_ref())
+    }
+
+    fn remove(&mut self, token: Token) {
+        if let Some(entry) = self.entries.get_mut(token.0) {
+            if entry.take().is_some() {
+                self.free.push(token.0);
+            }
+        }
+    }
+}
+
+impl Source {
+    fn fire(&self, evset: EventSet, wakers: &mut Vec<task::Waker>) {
+        let mut state = self.state.lock().unwrap();
+        state.armed = EventSet::empty();
+
+        // Errors and hangups are reported to both directions, whose next I/O will observe them.
+        let both = evset.is_error() || evset.is_hup();
+        if evset.is_readable() || both {
+            state.ready.insert(EventSet::readable());
+        }
+        if evset.is_writable() || both {
+            state.ready.insert(EventSet::writable());
+        }
+
+        // Both directions share the oneshot registration, which is now disarmed, so a task still
+        // waiting in the other direction is woken too, to re-arm it.
+        wakers.extend(state.reader.take());
+        wakers.extend(state.writer.take());
+    }
+}
+
+/// A source registered with a `Reactor`.
+///
+/// Readiness is tracked separately for reading and writing. A task polls for readiness in a
+/// direction, attempts its I/O once ready, and clears the readiness if the I/O would block, after
+/// which the next poll waits for the `Reactor` to report the direction ready again.
+pub struct Registration {
+    reactor: Reactor,
+    token: Token,
+    source: Arc<Source>,
+}
+
+impl Registration {
+    /// Returns `Ready` if the source is ready for reading, and otherwise arranges for the current
+    /// task to be woken once it is.
+    pub fn poll_read_ready(&self, cx: &mut Context) -> Poll<Result<()>> {
+        self.poll_ready(EventSet::readable(), cx)
+    }
+
+    /// Returns `Ready` if the source is ready for writing, and otherwise arranges for the current
+    /// task to be woken once it is.
+    pub fn poll_write_ready(&self, cx: &mut Context) -> Poll<Result<()>> {
+        self.poll_ready(EventSet::writable(), cx)
+    }
+
+    /// Returns the `Reactor` the source is registered with.
+    pub fn reactor(&self) -> &Reactor {
+        &self.reactor
+    }
+
+    /// Marks the source as not ready for reading, after a read fails with `WouldBlock`.
+    pub fn clear_read_ready(&self) {
+        self.source.state.lock().unwrap().ready.remove(EventSet::readable());
+    }
+
+    /// Marks the source as not ready for writing, after a write fails with `WouldBlock`.
+    pub fn clear_write_ready(&self) {
+        self.source.state.lock().unwrap().ready.remove(EventSet::writable());
+    }
+
+    fn poll_ready(&self, direction: EventSet, cx: &mut Context) -> Poll<Result<()>> {
+        let mut state = self.source.state.lock().unwrap();
+        if state.ready.contains(direction) {
+            return Poll::Ready(Ok(()));
+        }
+
+        {
+            let waiter = if direction == EventSet::readable() {
+                &mut state.reader
+            } else {
+                &mut state.writer
+            };
+            match *waiter {
+                Some(ref waker) if waker.will_wake(cx.waker()) => {}
+                _ => *waiter = Some(cx.waker().clone()),
+            }
+        }
+
+        // Re-arming reports readiness that arrived since the last event, so none is missed.
+        if !state.armed.contains(direction) {
+            let mut interest = EventSet::empty();
+            if state.reader.is_some() {
+                interest.insert(EventSet::readable());
+            }
+            if state.writer.is_some() {
+                interest.insert(EventSet::writable());
+            }
+
+            let res = self.reactor
+                .selector()
+                .reregister(self.source.fd, self.token, interest, PollOpt::oneshot());
+            if let Err(err) = res {
+                return Poll::Ready(Err(err));
+            }
+            state.armed = interest;
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let _ = self.reactor.selector().deregister(self.source.fd);
+        self.reactor.inner.sources.lock().unwrap().remove(self.token);
+    }
+}
+
+impl fmt::Debug for Registration {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Registration")
+            .field("token", &self.token)
+            .field("fd", &self.source.fd)
+            .finish()
+    }
+}
+
+/// A nonblocking source driven by a `Reactor`, with poll methods for use in futures.
+///
+/// Each poll method attempts the I/O when the source is ready and, if it would block, waits for
+/// readiness on the current task.
+#[derive(Debug)]
+pub struct Async<T> {
+    // Declared first so that it deregisters before `io` is closed.
+    registration: Registration,
+    io: T,
+}
+
+impl<T: AsRawFd> Async<T> {
+    /// Registers the nonblocking `io` with `reactor`.
+    pub fn new(io: T, reactor: &Reactor) -> Result<Async<T>> {
+        Ok(Async {
+            registration: try!(reactor.register(&io)),
+            io: io,
+        })
+    }
+}
+
+impl<T> Async<T> {
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    pub fn registration(&self) -> &Registration {
+        &self.registration
+    }
+
+    /// Deregisters and returns the source.
+    pub fn into_inner(self) -> T {
+        let Async { registration, io } = self;
+        drop(registration);
+        io
+    }
+
+    /// Calls `f` once the source is ready for reading, until it does not fail with `WouldBlock`.
+    pub fn poll_read_with<F, R>(&mut self, cx: &mut Context, mut f: F) -> Poll<Result<R>>
+        where F: FnMut(&mut T) -> Result<R>
+    {
+        loop {
+            match self.registration.poll_read_ready(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            match f(&mut self.io) {
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.registration.clear_read_ready()
+                }
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+
+    /// Calls `f` once the source is ready for writing, until it does not fail with `WouldBlock`.
+    pub fn poll_write_with<F, R>(&mut self, cx: &mut Context, mut f: F) -> Poll<Result<R>>
+        where F: FnMut(&mut T) -> Result<R>
+    {
+        loop {
+            match self.registration.poll_write_ready(cx) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            match f(&mut self.io) {
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => {
+                    self.registration.clear_write_ready()
+                }
+                res => return Poll::Ready(res),
+            }
+        }
+    }
+}
+
+impl<T: Read> Async<T> {
+    /// Reads into `buf`, waiting for the source to become readable if no data is available.
+    pub fn poll_read(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll<Result<usize>> {
+        self.poll_read_with(cx, |io| io.read(buf))
+    }
+}
+
+impl<T: Write> Async<T> {
+    /// Writes from `buf`, waiting for the source to become writable if it cannot accept data.
+    pub fn poll_write(&mut self, cx: &mut Context, buf: &[u8]) -> Poll<Result<usize>> {
+        self.poll_write_with(cx, |io| io.write(buf))
+    }
+
+    pub fn poll_flush(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        self.poll_write_with(cx, |io| io.flush())
+    }
+}
+
+struct TimerState {
+    // Set when the timeout expires, cleared when it is rescheduled.
+    fired: bool,
+    waker: Option<task::Waker>,
+}
+
+// A deadline on a `Reactor`, which wakes the task polling it once it has passed.
+pub(crate) struct Timer {
+    reactor: Reactor,
+    token: Token,
+    timeout: Timeout,
+    deadline: Instant,
+    state: Arc<Mutex<TimerState>>,
+}
+
+impl Timer {
+    pub(crate) fn new(reactor: &Reactor, deadline: Instant) -> Timer {
+        let state = Arc::new(Mutex::new(TimerState {
+            fired: false,
+            waker: None,
+        }));
+        let token = reactor.inner.timers.lock().unwrap().insert(state.clone());
+        let timeout = reactor.selector().set_timeout(token, until(deadline));
+
+        Timer {
+            reactor: reactor.clone(),
+            token: token,
+            timeout: timeout,
+            deadline: deadline,
+            state: state,
+        }
+    }
+
+    // Returns `Ready` once the deadline has passed.
+    pub(crate) fn poll_expired(&mut self, cx: &mut Context) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        let mut state = self.state.lock().unwrap();
+        match state.waker {
+            Some(ref waker) if waker.will_wake(cx.waker()) => {}
+            _ => state.waker = Some(cx.waker().clone()),
+        }
+
+        // The timer wheel may fire a tick early, in which case the timeout is scheduled anew.
+        if state.fired {
+            state.fired = false;
+            let mut selector = self.reactor.selector();
+            selector.cancel_timeout(self.timeout);
+            self.timeout = selector.set_timeout(self.token, until(self.deadline));
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.reactor.selector().cancel_timeout(self.timeout);
+        self.reactor.inner.timers.lock().unwrap().remove(self.token);
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Timer")
+            .field("token", &self.token)
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+fn until(deadline: Instant) -> Duration {
+    let now = Instant::now();
+    if deadline > now {
+        deadline - now
+    } else {
+        Duration::from_secs(0)
+    }
+}
@@ -0,0 +1,107 @@
+use std::future::Future;
+use std::io::{self, Result};
+use std::net::SocketAddr;
+use std::os::unix::io::AsRawFd;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use net::{TcpListener, TcpStream, UnixListener, UnixStream, UnixAddr};
+use reactor::Async;
+
+/// A listening socket from which connections are accepted.
+pub trait Listener: AsRawFd {
+    type Stream: AsRawFd;
+    type Addr;
+
+    /// Accepts a connection without blocking.
+    fn accept(&self) -> Result<(Self::Stream, Self::Addr)>;
+}
+
+impl Listener for TcpListener {
+    type Stream = TcpStream;
+    type Addr = SocketAddr;
+
+    fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
+        TcpListener::accept(self)
+    }
+}
+
+impl Listener for UnixListener {
+    type Stream = UnixStream;
+    type Addr = UnixAddr;
+
+    fn accept(&self) -> Result<(UnixStream, UnixAddr)> {
+        UnixListener::accept(self)
+    }
+}
+
+/// Returns a future that reads from `io` into `buf`, resolving to the number of bytes read.
+pub fn read<'a, T: io::Read>(io: &'a mut Async<T>, buf: &'a mut [u8]) -> Read<'a, T> {
+    Read { io: io, buf: buf }
+}
+
+/// Returns a future that writes from `buf` to `io`, resolving to the number of bytes written.
+pub fn write<'a, T: io::Write>(io: &'a mut Async<T>, buf: &'a [u8]) -> Write<'a, T> {
+    Write { io: io, buf: buf }
+}
+
+/// Returns a future that accepts a connection from `listener`.
+///
+/// The accepted stream is registered with the same `Reactor` as `listener`.
+pub fn accept<L: Listener>(listener: &mut Async<L>) -> Accept<L> {
+    Accept { listener: listener }
+}
+
+/// A future created by `read`.
+#[derive(Debug)]
+pub struct Read<'a, T: 'a> {
+    io: &'a mut Async<T>,
+    buf: &'a mut [u8],
+}
+
+impl<'a, T: io::Read> Future for Read<'a, T> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        this.io.poll_read(cx, this.buf)
+    }
+}
+
+/// A future created by `write`.
+#[derive(Debug)]
+pub struct Write<'a, T: 'a> {
+    io: &'a mut Async<T>,
+    buf: &'a [u8],
+}
+
+impl<'a, T: io::Write> Future for Write<'a, T> {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        this.io.poll_write(cx, this.buf)
+    }
+}
+
+/// A future created by `accept`.
+#[derive(Debug)]
+pub struct Accept<'a, L: 'a> {
+    listener: &'a mut Async<L>,
+}
+
+impl<'a, L: Listener> Future for Accept<'a, L> {
+    type Output = Result<(Async<L::Stream>, L::Addr)>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let listener = &mut self.get_mut().listener;
+        let (stream, addr) = match listener.poll_read_with(cx, |listener| listener.accept()) {
+            Poll::Ready(Ok(accepted)) => accepted,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let reactor = listener.registration().reactor();
+        Poll::Ready(Async::new(stream, reactor).map(|stream| (stream, addr)))
+    }
+}
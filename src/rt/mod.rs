@@ -0,0 +1,333 @@
+//! A single-threaded executor for futures driven by a `Reactor`.
+//!
+//! `block_on` runs a future to completion on the current thread, along with any tasks spawned
+//! onto its `LocalExecutor`. Once every task is waiting, the executor blocks in the `Selector`
+//! until a source becomes ready or the nearest timer expires.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::io::Result;
+use std::mem;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+use reactor::Reactor;
+
+mod io;
+pub use self::io::{read, write, accept, Read, Write, Accept, Listener};
+mod time;
+pub use self::time::{sleep, sleep_until, Sleep};
+
+// The task id woken to poll the future passed to `block_on`.
+const MAIN: usize = !0;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Reactor>> = const { RefCell::new(None) };
+}
+
+/// Runs `future` to completion on a new `LocalExecutor`.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    try!(LocalExecutor::new()).block_on(future)
+}
+
+/// Returns the `Reactor` of the executor running on the current thread.
+///
+/// # Panics
+///
+/// Panics if called outside of `block_on`.
+pub fn reactor() -> Reactor {
+    CURRENT.with(|current| current.borrow().clone())
+        .expect("not running on a rivet executor")
+}
+
+/// An executor running futures on a single thread.
+///
+/// Tasks need not be `Send`. A `LocalExecutor` is a handle; clones share the same tasks, so tasks
+/// may hold a clone with which to spawn more.
+#[derive(Clone)]
+pub struct LocalExecutor {
+    inner: Rc<Local>,
+}
+
+struct Local {
+    reactor: Reactor,
+    tasks: RefCell<Tasks>,
+    shared: Arc<Shared>,
+}
+
+// Tasks indexed by id. A task is taken out of its slot while it is being polled.
+struct Tasks {
+    entries: Vec<Option<Task>>,
+    free: Vec<usize>,
+}
+
+struct Task {
+    future: Pin<Box<dyn Future<Output = ()>>>,
+    waker: Arc<TaskWaker>,
+}
+
+// The state shared with wakers, which may be used from any thread.
+struct Shared {
+    reactor: Reactor,
+    // Ids of the tasks woken since they were last polled.
+    queue: Mutex<VecDeque<usize>>,
+    // Set while the executor is blocked in the `Reactor`, and so must be woken to run tasks.
+    parked: AtomicBool,
+}
+
+struct TaskWaker {
+    id: usize,
+    // Set while the task is queued, so that it is queued at most once.
+    scheduled: AtomicBool,
+    shared: Arc<Shared>,
+}
+
+impl LocalExecutor {
+    /// Creates a `LocalExecutor` with a `Reactor` of its own.
+    pub fn new() -> Result<LocalExecutor> {
+        Ok(LocalExecutor::with_reactor(try!(Reactor::new())))
+    }
+
+    /// Creates a `LocalExecutor` that drives `reactor`.
+    pub fn with_reactor(reactor: Reactor) -> LocalExecutor {
+        LocalExecutor {
+            inner: Rc::new(Local {
+                reactor: reactor.clone(),
+                tasks: RefCell::new(Tasks {
+                    entries: Vec::new(),
+                    free: Vec::new(),
+                }),
+                shared: Arc::new(Shared {
+                    reactor: reactor,
+                    queue: Mutex::new(VecDeque::new()),
+                    parked: AtomicBool::new(false),
+                }),
+            }),
+        }
+    }
+
+    pub fn reactor(&self) -> &Reactor {
+        &self.inner.reactor
+    }
+
+    /// Spawns a task running `future`, returning a handle that resolves to its output.
+    ///
+    /// Spawned tasks run while the executor is in `block_on`. Dropping the handle detaches the
+    /// task rather than cancelling it.
+    pub fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
+        where F: Future + 'static
+    {
+        let join = Rc::new(RefCell::new(Join {
+            output: None,
+            waker: None,
+        }));
+        let future = Spawned {
+            future: Box::pin(future),
+            join: join.clone(),
+        };
+
+        let mut tasks = self.inner.tasks.borrow_mut();
+        let id = match tasks.free.pop() {
+            Some(id) => id,
+            None => {
+                tasks.entries.push(None);
+                tasks.entries.len() - 1
+            }
+        };
+        tasks.entries[id] = Some(Task {
+            future: Box::pin(future),
+            waker: Arc::new(TaskWaker::new(id, &self.inner.shared)),
+        });
+        self.inner.shared.schedule(id);
+
+        JoinHandle { join: join }
+    }
+
+    /// Runs `future` to completion, running spawned tasks meanwhile.
+    ///
+    /// Tasks still pending when `future` completes remain spawned, and run in the next call.
+    /// Returns an error if polling the `Selector` fails.
+    pub fn block_on<F: Future>(&self, future: F) -> Result<F::Output> {
+        let _current = Current::enter(&self.inner.reactor);
+
+        let mut future = Box::pin(future);
+        let main = Arc::new(TaskWaker::new(MAIN, &self.inner.shared));
+        let waker = Waker::from(main.clone());
+        let shared = &self.inner.shared;
+        shared.schedule(MAIN);
+
+        loop {
+            // Tasks woken while the batch runs wait for the next, after the reactor is polled.
+            let mut batch = mem::replace(&mut *shared.queue.lock().unwrap(), VecDeque::new());
+            while let Some(id) = batch.pop_front() {
+                if id != MAIN {
+                    self.run(id);
+                    continue;
+                }
+
+                main.scheduled.store(false, Ordering::SeqCst);
+                let mut cx = Context::from_waker(&waker);
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    shared.queue.lock().unwrap().extend(batch);
+                    return Ok(output);
+                }
+            }
+
+            // A task woken once `parked` is set wakes the reactor, so the wakeup is not lost.
+            shared.parked.store(true, Ordering::SeqCst);
+            let timeout = if shared.queue.lock().unwrap().is_empty() {
+                None
+            } else {
+                Some(Duration::from_secs(0))
+            };
+            let res = self.inner.reactor.turn(timeout);
+            shared.parked.store(false, Ordering::SeqCst);
+            try!(res);
+        }
+    }
+
+    // Polls the task `id`, if it still exists.
+    fn run(&self, id: usize) {
+        let task = match self.inner.tasks.borrow_mut().entries.get_mut(id) {
+            Some(entry) => entry.take(),
+            None => None,
+        };
+        let mut task = match task {
+            Some(task) => task,
+            None => return,
+        };
+
+        task.waker.scheduled.store(false, Ordering::SeqCst);
+        let waker = Waker::from(task.waker.clone());
+        let res = task.future.as_mut().poll(&mut Context::from_waker(&waker));
+
+        let mut tasks = self.inner.tasks.borrow_mut();
+        match res {
+            Poll::Pending => tasks.entries[id] = Some(task),
+            Poll::Ready(()) => tasks.free.push(id),
+        }
+    }
+}
+
+impl fmt::Debug for LocalExecutor {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let tasks = self.inner.tasks.borrow();
+        fmt.debug_struct("LocalExecutor")
+            .field("reactor", &self.inner.reactor)
+            .field("tasks", &(tasks.entries.len() - tasks.free.len()))
+            .finish()
+    }
+}
+
+impl Shared {
+    fn schedule(&self, id: usize) {
+        self.queue.lock().unwrap().push_back(id);
+        if self.parked.load(Ordering::SeqCst) {
+            let _ = self.reactor.wake();
+        }
+    }
+}
+
+impl TaskWaker {
+    fn new(id: usize, shared: &Arc<Shared>) -> TaskWaker {
+        TaskWaker {
+            id: id,
+            scheduled: AtomicBool::new(true),
+            shared: shared.clone(),
+        }
+    }
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.scheduled.swap(true, Ordering::SeqCst) {
+            self.shared.schedule(self.id);
+        }
+    }
+}
+
+// Makes a `Reactor` current for the thread, restoring the previous one when dropped.
+struct Current {
+    previous: Option<Reactor>,
+}
+
+impl Current {
+    fn enter(reactor: &Reactor) -> Current {
+        let previous = CURRENT.with(|current| current.replace(Some(reactor.clone())));
+        Current { previous: previous }
+    }
+}
+
+impl Drop for Current {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+struct Join<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+// Runs a spawned future, passing its output to the `JoinHandle`.
+struct Spawned<F: Future> {
+    future: Pin<Box<F>>,
+    join: Rc<RefCell<Join<F::Output>>>,
+}
+
+impl<F: Future> Future for Spawned<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let output = match self.future.as_mut().poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        let mut join = self.join.borrow_mut();
+        join.output = Some(output);
+        if let Some(waker) = join.waker.take() {
+            waker.wake();
+        }
+        Poll::Ready(())
+    }
+}
+
+/// A future resolving to the output of a spawned task.
+pub struct JoinHandle<T> {
+    join: Rc<RefCell<Join<T>>>,
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let mut join = self.join.borrow_mut();
+        match join.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                join.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("JoinHandle")
+            .field("finished", &self.join.borrow().output.is_some())
+            .finish()
+    }
+}
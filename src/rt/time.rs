@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use reactor::Timer;
+
+// Stands in for deadlines too far off to be represented, about thirty years from now.
+const FAR_FUTURE: Duration = Duration::from_secs(86400 * 365 * 30);
+
+/// Returns a future that completes once `delay` has elapsed.
+///
+/// A `delay` too long to be represented is clamped to about thirty years.
+pub fn sleep(delay: Duration) -> Sleep {
+    let now = Instant::now();
+    sleep_until(now.checked_add(delay).unwrap_or(now + FAR_FUTURE))
+}
+
+/// Returns a future that completes once `deadline` has passed.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline: deadline,
+        timer: None,
+    }
+}
+
+/// A future completing at a deadline, created by `sleep` and `sleep_until`.
+///
+/// The timer is scheduled on the current executor's `Reactor` when the future is first polled,
+/// and cancelled if it is dropped before completing.
+///
+/// # Panics
+///
+/// Polling a `Sleep` outside of `block_on` panics.
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Instant,
+    timer: Option<Timer>,
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            self.timer = None;
+            return Poll::Ready(());
+        }
+
+        let deadline = self.deadline;
+        self.timer
+            .get_or_insert_with(|| Timer::new(&super::reactor(), deadline))
+            .poll_expired(cx)
+    }
+}
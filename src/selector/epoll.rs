@@ -1,27 +1,49 @@
 use std::os::unix::io::RawFd;
 use std::slice;
 use std::io::{Result, Error};
-use std::iter::{Iterator, DoubleEndedIterator, ExactSizeIterator};
-
+use std::time::Duration;
 
 use libc;
-use time::Duration;
 
-use event::EventSet;
+use event::{EventSet, PollOpt};
+use token::Token;
+use super::{Backend, Fired, timeout_ms};
 
 #[allow(dead_code)]
 mod ffi {
     use libc::c_int;
-    use event::EventSet;
+    use event::{EventSet, PollOpt};
 
     bitflags! {
         #[repr(C)]
         pub flags EpollFlag: c_int {
             const EPOLLIN = 0x001,
+            const EPOLLPRI = 0x002,
             const EPOLLOUT = 0x004,
             const EPOLLERR = 0x008,
             const EPOLLHUP = 0x010,
             const EPOLLRDHUP = 0x2000,
+            const EPOLLEXCLUSIVE = 1 << 28,
+            const EPOLLONESHOT = 1 << 30,
+            const EPOLLET = 1 << 31,
+        }
+    }
+
+    impl From<PollOpt> for EpollFlag {
+        fn from(opts: PollOpt) -> EpollFlag {
+            let mut epflag = EpollFlag::empty();
+
+            if opts.is_edge() {
+                epflag.insert(EPOLLET);
+            }
+            if opts.is_oneshot() {
+                epflag.insert(EPOLLONESHOT);
+            }
+            if opts.is_exclusive() {
+                epflag.insert(EPOLLEXCLUSIVE);
+            }
+
+            epflag
         }
     }
 
@@ -35,6 +57,9 @@ mod ffi {
             if evts.is_writable() {
                 epflag.insert(EPOLLOUT);
             }
+            if evts.is_priority() {
+                epflag.insert(EPOLLPRI);
+            }
             if evts.is_error() {
                 epflag.insert(EPOLLERR);
             }
@@ -56,6 +81,9 @@ mod ffi {
             if self.contains(EPOLLOUT) {
                 evts.insert(EventSet::writable());
             }
+            if self.contains(EPOLLPRI) {
+                evts.insert(EventSet::priority());
+            }
             if self.contains(EPOLLERR) {
                 evts.insert(EventSet::error());
             }
@@ -123,12 +151,15 @@ fn epoll_ctl(epfd: RawFd, op: ffi::EpollOp, fd: RawFd, event: &ffi::epoll_event)
     }
 }
 
-fn epoll_wait(epfd: RawFd, events: &mut [ffi::epoll_event], timeout: Duration) -> Result<usize> {
+fn epoll_wait(epfd: RawFd,
+              events: &mut [ffi::epoll_event],
+              timeout: Option<Duration>)
+              -> Result<usize> {
     let res = unsafe {
         ffi::epoll_wait(epfd,
                         events.as_mut_ptr(),
                         events.len() as libc::c_int,
-                        timeout.num_milliseconds() as libc::c_int)
+                        timeout_ms(timeout))
     };
 
     if res == -1 {
@@ -139,27 +170,25 @@ fn epoll_wait(epfd: RawFd, events: &mut [ffi::epoll_event], timeout: Duration) -
 }
 
 
-pub struct Selector {
+#[derive(Debug)]
+pub struct Epoll {
     epfd: RawFd,
     events: Vec<ffi::epoll_event>,
 }
 
-impl Selector {
-    pub fn new() -> Result<Selector> {
+impl Epoll {
+    pub fn new() -> Result<Epoll> {
         let epfd = try!(epoll_create());
 
-        Ok(Selector {
+        Ok(Epoll {
             epfd: epfd,
             events: Vec::with_capacity(1024),
         })
     }
+}
 
-    pub fn poll(&mut self) -> Result<IterFired> {
-        let timeout = Duration::milliseconds(-1);
-        self.poll_timeout(timeout)
-    }
-
-    pub fn poll_timeout(&mut self, timeout: Duration) -> Result<IterFired> {
+impl Backend for Epoll {
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()> {
         // Pass kernel the entire length of the `events` buffer, it will overwrite the memory as
         // needed and return the new length.
         let dst =
@@ -173,28 +202,41 @@ impl Selector {
             self.events.set_len(nevents);
         }
 
-        Ok(IterFired(self.events.iter()))
+        fired.extend(self.events.iter().map(|epev| {
+            Fired {
+                token: Token(epev.data as usize),
+                evset: epev.events.into(),
+                timeout: false,
+            }
+        }));
+
+        Ok(())
     }
 
-    pub fn register(&mut self, fd: RawFd, evts: EventSet) -> Result<()> {
+    fn register(&mut self, fd: RawFd, token: Token, evts: EventSet, opts: PollOpt) -> Result<()> {
         let evt = ffi::epoll_event {
-            events: evts.into(),
-            data: fd as u64,
+            events: ffi::EpollFlag::from(evts) | ffi::EpollFlag::from(opts),
+            data: token.0 as u64,
         };
 
         epoll_ctl(self.epfd, ffi::EPOLL_CTL_ADD, fd, &evt)
     }
 
-    pub fn reregister(&mut self, fd: RawFd, evts: EventSet) -> Result<()> {
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evts: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
         let evt = ffi::epoll_event {
-            events: evts.into(),
-            data: fd as u64,
+            events: ffi::EpollFlag::from(evts) | ffi::EpollFlag::from(opts),
+            data: token.0 as u64,
         };
 
         epoll_ctl(self.epfd, ffi::EPOLL_CTL_MOD, fd, &evt)
     }
 
-    pub fn deregister(&mut self, fd: RawFd) -> Result<()> {
+    fn deregister(&mut self, fd: RawFd) -> Result<()> {
         let evt = ffi::epoll_event {
             events: ffi::EpollFlag::empty(),
             data: 0,
@@ -204,54 +246,8 @@ impl Selector {
     }
 }
 
-impl Drop for Selector {
+impl Drop for Epoll {
     fn drop(&mut self) {
         let _ = unsafe { libc::close(self.epfd) };
     }
 }
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Fired {
-    fd: RawFd,
-    evset: EventSet,
-}
-
-impl Fired {
-    pub fn fd(&self) -> RawFd {
-        self.fd
-    }
-
-    pub fn evset(&self) -> EventSet {
-        self.evset
-    }
-
-    fn from_epoll(epev: &ffi::epoll_event) -> Fired {
-        Fired {
-            fd: epev.data as RawFd,
-            evset: epev.events.into(),
-        }
-    }
-}
-
-/// Iterator over the fired events of a `Selector`.
-pub struct IterFired<'a>(slice::Iter<'a, ffi::epoll_event>);
-
-impl<'a> Iterator for IterFired<'a> {
-    type Item = Fired;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(Fired::from_epoll)
-    }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
-    }
-}
-
-impl<'a> ExactSizeIterator for IterFired<'a> {}
-
-impl<'a> DoubleEndedIterator for IterFired<'a> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        self.0.next_back().map(Fired::from_epoll)
-    }
-}
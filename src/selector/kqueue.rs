@@ -1,12 +1,15 @@
 use std::os::unix::io::RawFd;
 use std::io::{Result, Error};
 use std::ptr;
+use std::cmp;
 use std::slice;
 
 use libc;
-use time::Duration;
+use std::time::Duration;
 
-use event::EventSet;
+use event::{EventSet, PollOpt};
+use token::Token;
+use super::{Backend, Fired};
 
 #[allow(dead_code)]
 mod ffi {
@@ -34,7 +37,7 @@ mod ffi {
 
     bitflags! {
         #[repr(C)]
-        flags EventFlag: libc::c_ushort {
+        pub flags EventFlag: libc::c_ushort {
             const EV_ADD = 0x0001,
             const EV_DELETE = 0x0002,
             const EV_ENABLE = 0x0004,
@@ -104,18 +107,16 @@ fn kevent(kq: RawFd,
           eventlist: &mut [ffi::kevent],
           timeout: Option<Duration>)
           -> Result<usize> {
-    let tspec = if let Some(dur) = timeout {
-        let sec = dur.num_seconds() as libc::time_t;
-        let nsec = (dur - Duration::seconds(sec))
-            .num_nanoseconds()
-            .unwrap() as libc::c_long;
-
-        &libc::timespec {
-            tv_sec: sec,
-            tv_nsec: nsec,
-        } as *const libc::timespec
-    } else {
-        ptr::null()
+    // Durations too large for a `time_t` are clamped.
+    let tspec = timeout.map(|dur| {
+        libc::timespec {
+            tv_sec: cmp::min(dur.as_secs(), libc::time_t::max_value() as u64) as libc::time_t,
+            tv_nsec: dur.subsec_nanos() as libc::c_long,
+        }
+    });
+    let tspec = match tspec {
+        Some(ref tspec) => tspec as *const libc::timespec,
+        None => ptr::null(),
     };
 
     let res = unsafe {
@@ -136,66 +137,65 @@ fn kevent(kq: RawFd,
 }
 
 #[derive(Debug)]
-pub struct Selector {
+pub struct Kqueue {
     kqfd: RawFd,
     events: Vec<ffi::kevent>,
 }
 
-impl Selector {
-    pub fn new() -> Result<Selector> {
+impl Kqueue {
+    pub fn new() -> Result<Kqueue> {
         let kqfd = try!(kqueue());
 
-        Ok(Selector {
+        Ok(Kqueue {
             kqfd: kqfd,
             events: Vec::with_capacity(1024),
         })
     }
+}
 
-    pub fn poll(&mut self) -> Result<IterFired> {
+impl Backend for Kqueue {
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()> {
         let dst =
             unsafe { slice::from_raw_parts_mut(self.events.as_mut_ptr(), self.events.capacity()) };
 
-        let nevents = try!(kevent(self.kqfd, &[], dst, None));
+        let nevents = try!(kevent(self.kqfd, &[], dst, timeout));
 
         unsafe {
             self.events.set_len(nevents);
         }
 
-        Ok(IterFired(self.events.iter()))
-    }
-
-    pub fn poll_timeout(&mut self, timeout: Duration) -> Result<IterFired> {
-        let dst =
-            unsafe { slice::from_raw_parts_mut(self.events.as_mut_ptr(), self.events.capacity()) };
+        fired.extend(self.events.iter().map(from_kevent));
 
-        let nevents = try!(kevent(self.kqfd, &[], dst, Some(timeout)));
+        Ok(())
+    }
 
-        unsafe {
-            self.events.set_len(nevents);
+    fn register(&mut self, fd: RawFd, token: Token, evts: EventSet, opts: PollOpt) -> Result<()> {
+        let mut flags = ffi::EV_ADD;
+        if opts.is_edge() {
+            flags.insert(ffi::EV_CLEAR);
+        }
+        if opts.is_oneshot() {
+            flags.insert(ffi::EV_ONESHOT);
         }
 
-        Ok(IterFired(self.events.iter()))
-
-    }
-
-    pub fn register(&mut self, fd: RawFd, evts: EventSet) -> Result<()> {
         let mut ke = ffi::kevent {
             ident: fd as usize,
-            flags: ffi::EV_ADD,
+            flags: flags,
+            udata: token.0,
             ..Default::default()
         };
 
         if evts.is_readable() {
             ke = ffi::kevent {
                 filter: ffi::EVFILT_READ,
-                flags: ke.flags | ffi::EV_ENABLE,
+                flags: flags | ffi::EV_ENABLE,
                 ..ke
             };
             try!(kevent(self.kqfd, &[ke], &mut [], None));
         } else {
             ke = ffi::kevent {
                 filter: ffi::EVFILT_READ,
-                flags: ke.flags | ffi::EV_DISABLE,
+                flags: flags | ffi::EV_DISABLE,
                 ..ke
             };
             try!(kevent(self.kqfd, &[ke], &mut [], None));
@@ -203,14 +203,14 @@ impl Selector {
         if evts.is_writable() {
             ke = ffi::kevent {
                 filter: ffi::EVFILT_WRITE,
-                flags: ke.flags | ffi::EV_ENABLE,
+                flags: flags | ffi::EV_ENABLE,
                 ..ke
             };
             try!(kevent(self.kqfd, &[ke], &mut [], None));
         } else {
             ke = ffi::kevent {
                 filter: ffi::EVFILT_WRITE,
-                flags: ke.flags | ffi::EV_DISABLE,
+                flags: flags | ffi::EV_DISABLE,
                 ..ke
             };
             try!(kevent(self.kqfd, &[ke], &mut [], None));
@@ -220,11 +220,16 @@ impl Selector {
         Ok(())
     }
 
-    pub fn reregister(&mut self, fd: RawFd, evts: EventSet) -> Result<()> {
-        self.register(fd, evts)
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evts: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        self.register(fd, token, evts, opts)
     }
 
-    pub fn deregister(&mut self, fd: RawFd) -> Result<()> {
+    fn deregister(&mut self, fd: RawFd) -> Result<()> {
         let ke = ffi::kevent {
             ident: fd as usize,
             flags: ffi::EV_DELETE,
@@ -241,54 +246,25 @@ impl Selector {
     }
 }
 
-impl Drop for Selector {
+impl Drop for Kqueue {
     fn drop(&mut self) {
         let _ = unsafe { libc::close(self.kqfd) };
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Fired {
-    fd: RawFd,
-    evset: EventSet,
-}
-
-impl Fired {
-    pub fn fd(&self) -> RawFd {
-        self.fd
-    }
-
-    pub fn evset(&self) -> EventSet {
-        self.evset
-    }
-
-    fn from_kevent(kevt: &ffi::kevent) -> Fired {
-        Fired {
-            fd: kevt.ident as RawFd,
-            evset: kevt.filter.into(),
-        }
-    }
-}
-
-/// Iterator over the fired events of a `Selector`.
-pub struct IterFired<'a>(slice::Iter<'a, ffi::kevent>);
-
-impl<'a> Iterator for IterFired<'a> {
-    type Item = Fired;
+fn from_kevent(kevt: &ffi::kevent) -> Fired {
+    let mut evset: EventSet = kevt.filter.into();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.0.next().map(Fired::from_kevent)
+    if kevt.flags.contains(ffi::EV_EOF) {
+        evset.insert(EventSet::hup());
     }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+    if kevt.flags.contains(ffi::EV_ERROR) {
+        evset.insert(EventSet::error());
     }
-}
-
-impl<'a> ExactSizeIterator for IterFired<'a> {}
 
-impl<'a> DoubleEndedIterator for IterFired<'a> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        self.0.next_back().map(Fired::from_kevent)
+    Fired {
+        token: Token(kevt.udata),
+        evset: evset,
+        timeout: false,
     }
 }
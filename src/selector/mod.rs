@@ -1,28 +1,449 @@
-#[cfg(all(not(feature = "select"),
-target_os = "linux"))]
+use std::cmp;
+use std::fmt;
+use std::slice;
+use std::os::unix::io::RawFd;
+use std::io::{Result, Error, ErrorKind};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use libc;
+
+use event::{EventSet, PollOpt};
+use token::Token;
+use waker;
+use wheel::{Wheel, Timeout};
+
+#[cfg(target_os = "linux")]
 mod epoll;
-#[cfg(all(not(feature = "select"),
-target_os = "linux"))]
-pub use self::epoll::{Selector, Iter, Fired};
-
-#[cfg(all(not(feature = "select"),
-any(target_os = "freebsd",
-    target_os = "openbsd",
-    target_os = "netbsd",
-    target_os = "bitrig",
-    target_os = "dragonfly")))]
+
+#[cfg(all(target_os = "linux", feature = "io_uring"))]
+mod uring;
+
+#[cfg(any(target_os = "freebsd",
+          target_os = "openbsd",
+          target_os = "netbsd",
+          target_os = "bitrig",
+          target_os = "dragonfly"))]
 mod kqueue;
-#[cfg(all(not(feature = "select"),
-any(target_os = "freebsd",
-    target_os = "openbsd",
-    target_os = "netbsd",
-    target_os = "bitrig",
-    target_os = "dragonfly")))]
-pub use self::kqueue::{Selector, Iter, Fired};
-
-#[cfg(any(feature = "select",
-          target_os = "macos"))]
+
+mod poll;
+
 mod select;
-#[cfg(any(feature = "select",
-          target_os = "macos"))]
-pub use self::select::{Selector, Iter, Fired};
+
+/// The interface implemented by each selector backend.
+pub trait Backend {
+    /// Waits up to `timeout` for readiness on the registered file descriptors, appending an event
+    /// to `fired` for each one that is ready.
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()>;
+
+    fn register(&mut self, fd: RawFd, token: Token, evset: EventSet, opts: PollOpt) -> Result<()>;
+
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()>;
+
+    fn deregister(&mut self, fd: RawFd) -> Result<()>;
+}
+
+/// The mechanisms a `Selector` can use to wait for readiness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BackendKind {
+    /// `epoll(7)`, available on Linux.
+    Epoll,
+    /// `io_uring(7)` polls, available on Linux 5.13 and later with the `io_uring` feature.
+    Uring,
+    /// `kqueue(2)`, available on the BSDs.
+    Kqueue,
+    /// `poll(2)`, available everywhere.
+    Poll,
+    /// `select(2)`, available everywhere but limited to `FD_SETSIZE` descriptors.
+    Select,
+}
+
+impl Default for BackendKind {
+    /// Returns the backend used by `Selector::new`.
+    ///
+    /// This is the native backend of the platform unless overridden by the `select`, `poll` or
+    /// `io_uring` cargo features.
+    fn default() -> BackendKind {
+        if cfg!(feature = "select") {
+            BackendKind::Select
+        } else if cfg!(feature = "poll") {
+            BackendKind::Poll
+        } else if cfg!(all(target_os = "linux", feature = "io_uring")) {
+            BackendKind::Uring
+        } else if cfg!(target_os = "linux") {
+            BackendKind::Epoll
+        } else if cfg!(any(target_os = "freebsd",
+                           target_os = "openbsd",
+                           target_os = "netbsd",
+                           target_os = "bitrig",
+                           target_os = "dragonfly")) {
+            BackendKind::Kqueue
+        } else {
+            BackendKind::Select
+        }
+    }
+}
+
+impl BackendKind {
+    /// Returns every backend compiled into this build, in order of preference.
+    ///
+    /// `io_uring` comes last, as it depends on the running kernel, which may not support or permit
+    /// a ring even when the feature is compiled in.
+    pub fn available() -> Vec<BackendKind> {
+        let mut kinds = Vec::new();
+
+        if cfg!(target_os = "linux") {
+            kinds.push(BackendKind::Epoll);
+        }
+        if cfg!(any(target_os = "freebsd",
+                    target_os = "openbsd",
+                    target_os = "netbsd",
+                    target_os = "bitrig",
+                    target_os = "dragonfly")) {
+            kinds.push(BackendKind::Kqueue);
+        }
+        kinds.push(BackendKind::Poll);
+        kinds.push(BackendKind::Select);
+        if cfg!(all(target_os = "linux", feature = "io_uring")) {
+            kinds.push(BackendKind::Uring);
+        }
+
+        kinds
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    #[cfg(target_os = "linux")]
+    Epoll(epoll::Epoll),
+    #[cfg(all(target_os = "linux", feature = "io_uring"))]
+    Uring(uring::Uring),
+    #[cfg(any(target_os = "freebsd",
+              target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "dragonfly"))]
+    Kqueue(kqueue::Kqueue),
+    Poll(poll::Poll),
+    Select(select::Select),
+}
+
+// Forwards a call to whichever backend is in use.
+macro_rules! dispatch {
+    ($inner:expr, $backend:ident => $call:expr) => {
+        match $inner {
+            #[cfg(target_os = "linux")]
+            Inner::Epoll(ref mut $backend) => $call,
+            #[cfg(all(target_os = "linux", feature = "io_uring"))]
+            Inner::Uring(ref mut $backend) => $call,
+            #[cfg(any(target_os = "freebsd",
+                      target_os = "openbsd",
+                      target_os = "netbsd",
+                      target_os = "bitrig",
+                      target_os = "dragonfly"))]
+            Inner::Kqueue(ref mut $backend) => $call,
+            Inner::Poll(ref mut $backend) => $call,
+            Inner::Select(ref mut $backend) => $call,
+        }
+    }
+}
+
+/// Monitors a set of file descriptors for readiness.
+pub struct Selector {
+    inner: Inner,
+    fired: Vec<Fired>,
+    waker: Option<Arc<waker::Inner>>,
+    wheel: Wheel,
+}
+
+impl Selector {
+    /// Creates a `Selector` using the default backend.
+    ///
+    /// If the default is `io_uring` but a ring cannot be set up, because the kernel is too old or
+    /// `io_uring` is not permitted, falls back to `epoll`.
+    pub fn new() -> Result<Selector> {
+        let kind = BackendKind::default();
+
+        match Selector::with_backend(kind) {
+            Err(_) if kind == BackendKind::Uring => Selector::with_backend(BackendKind::Epoll),
+            res => res,
+        }
+    }
+
+    /// Creates a `Selector` using the given backend.
+    ///
+    /// Fails if the backend is not available on this platform.
+    pub fn with_backend(kind: BackendKind) -> Result<Selector> {
+        let inner = match kind {
+            #[cfg(target_os = "linux")]
+            BackendKind::Epoll => Inner::Epoll(try!(epoll::Epoll::new())),
+            #[cfg(all(target_os = "linux", feature = "io_uring"))]
+            BackendKind::Uring => Inner::Uring(try!(uring::Uring::new())),
+            #[cfg(any(target_os = "freebsd",
+                      target_os = "openbsd",
+                      target_os = "netbsd",
+                      target_os = "bitrig",
+                      target_os = "dragonfly"))]
+            BackendKind::Kqueue => Inner::Kqueue(try!(kqueue::Kqueue::new())),
+            BackendKind::Poll => Inner::Poll(try!(poll::Poll::new())),
+            BackendKind::Select => Inner::Select(try!(select::Select::new())),
+            _ => {
+                return Err(Error::new(ErrorKind::Other,
+                                      "selector backend not available on this platform"))
+            }
+        };
+
+        Ok(Selector {
+            inner: inner,
+            fired: Vec::with_capacity(1024),
+            waker: None,
+            wheel: Wheel::new(),
+        })
+    }
+
+    /// Returns the backend in use by this `Selector`.
+    pub fn backend(&self) -> BackendKind {
+        match self.inner {
+            #[cfg(target_os = "linux")]
+            Inner::Epoll(..) => BackendKind::Epoll,
+            #[cfg(all(target_os = "linux", feature = "io_uring"))]
+            Inner::Uring(..) => BackendKind::Uring,
+            #[cfg(any(target_os = "freebsd",
+                      target_os = "openbsd",
+                      target_os = "netbsd",
+                      target_os = "bitrig",
+                      target_os = "dragonfly"))]
+            Inner::Kqueue(..) => BackendKind::Kqueue,
+            Inner::Poll(..) => BackendKind::Poll,
+            Inner::Select(..) => BackendKind::Select,
+        }
+    }
+
+    /// Waits for readiness on the registered file descriptors.
+    ///
+    /// Blocks until at least one event fires or `timeout` elapses. A timeout of `None` blocks
+    /// indefinitely. Pending timeouts bound the time spent blocking, and any that expire are
+    /// returned alongside the readiness events. A wait interrupted by a signal is resumed with the
+    /// time remaining.
+    pub fn poll(&mut self, timeout: Option<Duration>) -> Result<Iter> {
+        self.fired.clear();
+        let start = Instant::now();
+
+        loop {
+            let remaining = timeout.map(|dur| {
+                dur.checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0))
+            });
+
+            // Whether the wait is cut short by the next deadline of the wheel rather than by
+            // `timeout`.
+            let (wait, early) = match self.wheel.next_deadline() {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    let until = if deadline > now {
+                        deadline - now
+                    } else {
+                        Duration::from_secs(0)
+                    };
+                    match remaining {
+                        Some(dur) if dur <= until => (Some(dur), false),
+                        _ => (Some(until), true),
+                    }
+                }
+                None => (remaining, false),
+            };
+            match dispatch!(self.inner, backend => backend.poll(&mut self.fired, wait)) {
+                Ok(()) => {}
+                // Besides signals, tearing down an `io_uring` interrupts the next blocking call of
+                // each thread that entered it.
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+
+            let fired = &mut self.fired;
+            self.wheel.expire(Instant::now(), |token| {
+                fired.push(Fired {
+                    token: token,
+                    evset: EventSet::empty(),
+                    timeout: true,
+                })
+            });
+
+            // The deadline may have belonged to a timeout in a later rotation of the wheel, in
+            // which case nothing expired and the wait continues.
+            if !self.fired.is_empty() || !early {
+                break;
+            }
+        }
+
+        if let Some(ref waker) = self.waker {
+            if self.fired.iter().any(Fired::is_wakeup) {
+                try!(waker.reset());
+            }
+        }
+
+        Ok(Iter(self.fired.iter()))
+    }
+
+    /// Registers a file descriptor with the `Selector`.
+    ///
+    /// The given file descriptor will be monitored for the events specified in `evset`, and any
+    /// resulting `Fired` events will carry `token`.
+    pub fn register(&mut self,
+                    fd: RawFd,
+                    token: Token,
+                    evset: EventSet,
+                    opts: PollOpt)
+                    -> Result<()> {
+        dispatch!(self.inner, backend => backend.register(fd, token, evset, opts))
+    }
+
+    /// Re-registers a file descriptor with the `Selector`.
+    ///
+    /// Re-registration of a file descriptor allows for modification of its associated `EventSet`
+    /// and `PollOpt`, and re-arms a oneshot registration that has already fired.
+    pub fn reregister(&mut self,
+                      fd: RawFd,
+                      token: Token,
+                      evset: EventSet,
+                      opts: PollOpt)
+                      -> Result<()> {
+        dispatch!(self.inner, backend => backend.reregister(fd, token, evset, opts))
+    }
+
+    /// Deregisters a file descriptor with the `Selector`.
+    pub fn deregister(&mut self, fd: RawFd) -> Result<()> {
+        dispatch!(self.inner, backend => backend.deregister(fd))
+    }
+
+    /// Schedules a timeout for `token` once `delay` has elapsed.
+    ///
+    /// When it expires, `poll` returns a `Fired` carrying `token` for which `is_timeout` is true.
+    /// Timeouts are rounded up to the timer tick, which is one millisecond by default.
+    pub fn set_timeout(&mut self, token: Token, delay: Duration) -> Timeout {
+        self.wheel.insert(token, delay)
+    }
+
+    /// Cancels a pending timeout.
+    ///
+    /// Returns `false` if the timeout has already expired or been cancelled.
+    pub fn cancel_timeout(&mut self, timeout: Timeout) -> bool {
+        self.wheel.cancel(timeout)
+    }
+
+    /// Reschedules a pending timeout to expire once `delay` has elapsed.
+    ///
+    /// Returns `false` if the timeout has already expired or been cancelled.
+    pub fn reset_timeout(&mut self, timeout: Timeout, delay: Duration) -> bool {
+        self.wheel.reset(timeout, delay)
+    }
+
+    /// Configures the granularity of the timer and the number of slots in its wheel.
+    ///
+    /// A coarser tick means fewer wakeups at the cost of precision; more slots make long timeouts
+    /// cheaper to step over. Fails if any timeouts are pending or `tick` is zero.
+    pub fn set_timer_tick(&mut self, tick: Duration, slots: usize) -> Result<()> {
+        if tick == Duration::from_secs(0) || slots == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "timer tick and slots must be nonzero"));
+        }
+        if !self.wheel.is_empty() {
+            return Err(Error::new(ErrorKind::Other, "timeouts are pending"));
+        }
+
+        self.wheel = Wheel::with_tick(tick, slots);
+        Ok(())
+    }
+
+    pub(crate) fn waker(&self) -> Option<&Arc<waker::Inner>> {
+        self.waker.as_ref()
+    }
+
+    pub(crate) fn set_waker(&mut self, waker: Arc<waker::Inner>) {
+        self.waker = Some(waker);
+    }
+}
+
+impl fmt::Debug for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Selector")
+            .field("inner", &self.inner)
+            .field("waker", &self.waker)
+            .field("wheel", &self.wheel)
+            .finish()
+    }
+}
+
+/// A readiness event for a registered file descriptor, or an expired timeout.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fired {
+    token: Token,
+    evset: EventSet,
+    timeout: bool,
+}
+
+impl Fired {
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn evset(&self) -> EventSet {
+        self.evset
+    }
+
+    /// Returns `true` if this event was caused by the `Selector`'s `Waker`.
+    pub fn is_wakeup(&self) -> bool {
+        self.token == waker::TOKEN
+    }
+
+    /// Returns `true` if this event is an expired timeout rather than readiness.
+    ///
+    /// The `EventSet` of a timeout is always empty.
+    pub fn is_timeout(&self) -> bool {
+        self.timeout
+    }
+}
+
+/// Iterator over the fired events of a `Selector`.
+#[derive(Debug)]
+pub struct Iter<'a>(slice::Iter<'a, Fired>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Fired;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().cloned()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().cloned()
+    }
+}
+
+// Converts a timeout into milliseconds for `epoll_wait` and `poll`, where -1 blocks indefinitely.
+//
+// Sub-millisecond remainders are rounded up so that short timeouts do not degrade into a
+// busy-poll, and durations too large for a `c_int` are clamped.
+fn timeout_ms(timeout: Option<Duration>) -> libc::c_int {
+    match timeout {
+        Some(dur) => {
+            let ms = dur.as_secs()
+                .saturating_mul(1000)
+                .saturating_add((dur.subsec_nanos() as u64 + 999_999) / 1_000_000);
+
+            cmp::min(ms, libc::c_int::max_value() as u64) as libc::c_int
+        }
+        None => -1,
+    }
+}
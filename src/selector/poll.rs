@@ -0,0 +1,240 @@
+use std::os::unix::io::RawFd;
+use std::io::{Result, Error};
+use std::time::{Duration, Instant};
+use std::collections::HashMap;
+
+use libc;
+
+use event::{self, EventSet, PollOpt};
+use token::Token;
+use super::{Backend, Fired, timeout_ms};
+
+// Simple wrapper around the raw `poll` call.
+fn poll(fds: &mut [libc::pollfd], timeout: Option<Duration>) -> Result<usize> {
+    let res = unsafe {
+        libc::poll(fds.as_mut_ptr(),
+                   fds.len() as libc::nfds_t,
+                   timeout_ms(timeout))
+    };
+
+    if res == -1 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(res as usize)
+    }
+}
+
+fn interest(evset: EventSet) -> libc::c_short {
+    let mut events = 0;
+
+    if evset.is_readable() {
+        events |= libc::POLLIN;
+    }
+    if evset.is_writable() {
+        events |= libc::POLLOUT;
+    }
+    if evset.is_priority() {
+        events |= libc::POLLPRI;
+    }
+
+    events
+}
+
+fn readiness(revents: libc::c_short) -> EventSet {
+    let mut evset = EventSet::empty();
+
+    if revents & libc::POLLIN != 0 {
+        evset.insert(event::READABLE);
+    }
+    if revents & libc::POLLOUT != 0 {
+        evset.insert(event::WRITABLE);
+    }
+    if revents & libc::POLLPRI != 0 {
+        evset.insert(event::PRIORITY);
+    }
+    if revents & (libc::POLLERR | libc::POLLNVAL) != 0 {
+        evset.insert(event::ERROR);
+    }
+    if revents & libc::POLLHUP != 0 {
+        evset.insert(event::HUP);
+    }
+
+    evset
+}
+
+// State kept for each registered file descriptor, at the same index as its `pollfd`.
+//
+// `poll` has no notion of edge-triggered or oneshot registration, so both are emulated here.
+#[derive(Debug)]
+struct Registration {
+    fd: RawFd,
+    token: Token,
+    opts: PollOpt,
+    // Readiness last reported for an edge-triggered registration. Events are only reported again
+    // once `poll` has observed them as not ready.
+    reported: EventSet,
+}
+
+/// A set of file descriptors monitored with `poll(2)`.
+///
+/// Unlike `select`, there is no upper bound on the value of a registered file descriptor, and
+/// hangup and error conditions are reported.
+#[derive(Debug)]
+pub struct Poll {
+    // Descriptors handed to `poll`. A negative `fd` marks a oneshot registration that has fired.
+    pollfds: Vec<libc::pollfd>,
+    regs: Vec<Registration>,
+    // Position of each registered file descriptor in `pollfds` and `regs`.
+    index: HashMap<RawFd, usize>,
+}
+
+impl Poll {
+    pub fn new() -> Result<Poll> {
+        Ok(Poll {
+            pollfds: Vec::new(),
+            regs: Vec::new(),
+            index: HashMap::new(),
+        })
+    }
+
+    fn poll_events(&mut self,
+                   fired: &mut Vec<Fired>,
+                   timeout: Option<Duration>,
+                   suppressed: &mut Vec<usize>)
+                   -> Result<()> {
+        let start = Instant::now();
+        let nfired = fired.len();
+
+        loop {
+            let remaining = timeout.map(|dur| {
+                dur.checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0))
+            });
+
+            let nready = try!(poll(&mut self.pollfds, remaining));
+
+            let mut still_ready = Vec::new();
+
+            let regs = self.pollfds.iter_mut().zip(self.regs.iter_mut());
+            for (i, (pollfd, reg)) in regs.enumerate() {
+                // Disarmed or suppressed.
+                if pollfd.fd < 0 {
+                    continue;
+                }
+
+                let ready = readiness(pollfd.revents);
+
+                let evset = if reg.opts.is_edge() {
+                    let evset = ready - reg.reported;
+                    reg.reported = ready;
+                    evset
+                } else {
+                    ready
+                };
+
+                if evset.is_empty() {
+                    if !ready.is_empty() {
+                        still_ready.push(i);
+                    }
+                    continue;
+                }
+
+                if reg.opts.is_oneshot() {
+                    pollfd.fd = -1;
+                }
+
+                fired.push(Fired {
+                    token: reg.token,
+                    evset: evset,
+                    timeout: false,
+                });
+            }
+
+            // Only go around again if everything `poll` woke up for was suppressed.
+            if nready == 0 || fired.len() > nfired {
+                return Ok(());
+            }
+            for i in still_ready {
+                self.pollfds[i].fd = -1;
+                suppressed.push(i);
+            }
+        }
+    }
+}
+
+impl Backend for Poll {
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()> {
+        // Edge-triggered descriptors that are still ready from an earlier poll are left out of any
+        // retry so that `poll` does not keep returning immediately because of them.
+        let mut suppressed = Vec::new();
+        let res = self.poll_events(fired, timeout, &mut suppressed);
+
+        for i in suppressed {
+            self.pollfds[i].fd = self.regs[i].fd;
+        }
+
+        res
+    }
+
+    fn register(&mut self, fd: RawFd, token: Token, evset: EventSet, opts: PollOpt) -> Result<()> {
+        if self.index.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::EEXIST));
+        }
+
+        self.index.insert(fd, self.pollfds.len());
+        self.pollfds.push(libc::pollfd {
+            fd: fd,
+            events: interest(evset),
+            revents: 0,
+        });
+        self.regs.push(Registration {
+            fd: fd,
+            token: token,
+            opts: opts,
+            reported: EventSet::empty(),
+        });
+
+        Ok(())
+    }
+
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        let i = match self.index.get(&fd) {
+            Some(&i) => i,
+            None => return Err(Error::from_raw_os_error(libc::ENOENT)),
+        };
+
+        self.pollfds[i] = libc::pollfd {
+            fd: fd,
+            events: interest(evset),
+            revents: 0,
+        };
+        self.regs[i] = Registration {
+            fd: fd,
+            token: token,
+            opts: opts,
+            reported: EventSet::empty(),
+        };
+
+        Ok(())
+    }
+
+    fn deregister(&mut self, fd: RawFd) -> Result<()> {
+        let i = match self.index.remove(&fd) {
+            Some(i) => i,
+            None => return Err(Error::from_raw_os_error(libc::ENOENT)),
+        };
+
+        // Move the last registration into the vacated slot.
+        self.pollfds.swap_remove(i);
+        self.regs.swap_remove(i);
+        if i < self.regs.len() {
+            self.index.insert(self.regs[i].fd, i);
+        }
+
+        Ok(())
+    }
+}
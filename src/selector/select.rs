@@ -2,16 +2,19 @@ use std::ptr;
 use std::os::unix::io::RawFd;
 use std::cmp;
 use std::io::{Result, Error};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::mem;
 use std::fmt;
+use std::collections::HashMap;
 
 use libc;
-use event::{self, EventSet};
+use event::{self, EventSet, PollOpt};
+use token::Token;
+use super::{Backend, Fired};
 
 // Returns the highest file descriptor in the given `fd_set`, searching backwards from `prev_max`.
 fn find_max(set: &libc::fd_set, prev_max: RawFd) -> RawFd {
-    for i in prev_max..0 {
+    for i in (0..prev_max).rev() {
         let isset = unsafe { libc::FD_ISSET(i, set) };
         if isset {
             return i;
@@ -26,16 +29,24 @@ fn select(nfds: RawFd,
           wset: &mut libc::fd_set,
           timeout: Option<Duration>)
           -> Result<usize> {
-    let tv = if let Some(dur) = timeout {
-        let sec = dur.as_secs() as libc::time_t;
-        let usec = dur.subsec_nanos() as libc::suseconds_t;
-
-        &mut libc::timeval {
-            tv_sec: sec,
-            tv_usec: usec,
-        } as *mut libc::timeval
-    } else {
-        ptr::null_mut()
+    // Sub-microsecond remainders are rounded up and durations too large for a `time_t` are
+    // clamped.
+    let mut tv = timeout.map(|dur| {
+        let mut sec = dur.as_secs();
+        let mut usec = (dur.subsec_nanos() + 999) / 1000;
+        if usec == 1_000_000 {
+            sec = sec.saturating_add(1);
+            usec = 0;
+        }
+
+        libc::timeval {
+            tv_sec: cmp::min(sec, libc::time_t::max_value() as u64) as libc::time_t,
+            tv_usec: usec as libc::suseconds_t,
+        }
+    });
+    let tv = match tv {
+        Some(ref mut tv) => tv as *mut libc::timeval,
+        None => ptr::null_mut(),
     };
 
     let res = unsafe { libc::select(nfds, rset, wset, ptr::null_mut(), tv) };
@@ -47,191 +58,205 @@ fn select(nfds: RawFd,
     }
 }
 
+// State kept for each registered file descriptor.
+//
+// `select` has no notion of edge-triggered or oneshot registration, so both are emulated here.
+#[derive(Debug)]
+struct Registration {
+    token: Token,
+    opts: PollOpt,
+    // Readiness last reported for an edge-triggered registration. Events are only reported again
+    // once `select` has observed them as not ready.
+    reported: EventSet,
+}
+
 /// A set of file descriptors that can be monitored to determine readiness for I/O operations.
-pub struct Selector {
+pub struct Select {
     // Highest file descriptor in both `fd_set`s.
     maxfd: RawFd,
 
     rfds: libc::fd_set,
     wfds: libc::fd_set,
+
+    // Side table of registrations, as `select` has no room for user data.
+    regs: HashMap<RawFd, Registration>,
 }
 
-impl Selector {
-    /// Creates an empty `Selector`.
-    pub fn new() -> Result<Selector> {
+impl Select {
+    /// Creates an empty `Select`.
+    pub fn new() -> Result<Select> {
         unsafe {
-            Ok(Selector {
+            Ok(Select {
                 maxfd: 0,
                 rfds: mem::zeroed(),
                 wfds: mem::zeroed(),
+                regs: HashMap::new(),
             })
         }
     }
 
-    pub fn poll(&mut self) -> Result<Iter> {
-        // Clone the `fd_set`s as `select` will modify them.
-        let mut rfds = self.rfds.clone();
-        let mut wfds = self.wfds.clone();
-        let nfds = self.maxfd + 1;
-
-        try!(select(nfds, &mut rfds, &mut wfds, None));
-
-        Ok(Iter {
-            maxfd: self.maxfd,
-            curfd: 0,
-            rfds: rfds,
-            wfds: wfds,
-        })
-    }
-
-    pub fn poll_timeout(&mut self, timeout: Duration) -> Result<Iter> {
-        // Clone the `fd_set`s as select will modify them.
-        let mut rfds = self.rfds.clone();
-        let mut wfds = self.wfds.clone();
-        let nfds = self.maxfd + 1;
-
-        try!(select(nfds, &mut rfds, &mut wfds, Some(timeout)));
-
-        Ok(Iter {
-            maxfd: self.maxfd,
-            curfd: 0,
-            rfds: rfds,
-            wfds: wfds,
-        })
-    }
-
-    /// Registers a file descriptor with the `Selector`.
-    ///
-    /// The given file descriptor will be monitored for the events specified in `evset`.
-    pub fn register(&mut self, fd: RawFd, evset: EventSet) -> Result<()> {
-        if evset.is_readable() {
-            unsafe {
+    // Updates the `fd_set`s to monitor `fd` for exactly the events in `evset`.
+    fn set_interest(&mut self, fd: RawFd, evset: EventSet) {
+        unsafe {
+            if evset.is_readable() {
                 libc::FD_SET(fd, &mut self.rfds);
+            } else {
+                libc::FD_CLR(fd, &mut self.rfds);
             }
-            self.maxfd = cmp::max(fd, self.maxfd);
-        }
-        if evset.is_writable() {
-            unsafe {
+            if evset.is_writable() {
                 libc::FD_SET(fd, &mut self.wfds);
+            } else {
+                libc::FD_CLR(fd, &mut self.wfds);
             }
-            self.maxfd = cmp::max(fd, self.maxfd);
         }
 
-        Ok(())
-    }
-
-    /// Re-registers a file descriptor with the `Selector`.
-    ///
-    /// Re-registration of a file descriptor allows for modification of its associated `EventSet`.
-    pub fn reregister(&mut self, fd: RawFd, evset: EventSet) -> Result<()> {
         if evset.intersects(EventSet::readable() | EventSet::writable()) {
-            self.register(fd, evset)
-        } else {
-            self.deregister(fd)
-        }
-    }
-
-    /// Deregisters a file descriptor with the `Selector`.
-    pub fn deregister(&mut self, fd: RawFd) -> Result<()> {
-        unsafe {
-            libc::FD_CLR(fd, &mut self.rfds);
-            libc::FD_CLR(fd, &mut self.rfds);
-        }
-
-        // If we removed the highest file descriptor, find the new maximum.
-        if fd == self.maxfd {
+            self.maxfd = cmp::max(fd, self.maxfd);
+        } else if fd == self.maxfd {
+            // If we removed the highest file descriptor, find the new maximum.
             self.maxfd = cmp::max(find_max(&self.rfds, fd), find_max(&self.wfds, fd));
         }
-
-        Ok(())
     }
-}
-
-impl fmt::Debug for Selector {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Might as well give some useful debug info.
-        let mut rfds = Vec::new();
-        for i in 0..(self.maxfd + 1) {
-            let isset = unsafe { libc::FD_ISSET(i, &self.rfds) };
-            if isset {
-                rfds.push(i);
-            }
-        }
-
-        let mut wfds = Vec::new();
-        for i in 0..(self.maxfd + 1) {
-            let isset = unsafe { libc::FD_ISSET(i, &self.wfds) };
-            if isset {
-                wfds.push(i);
-            }
-        }
 
-        f.debug_struct("Selector")
-            .field("maxfd", &self.maxfd)
-            .field("rfds", &rfds)
-            .field("wfds", &wfds)
-            .finish()
+    // Records the registration of `fd`, replacing any previous one.
+    fn insert(&mut self, fd: RawFd, token: Token, evset: EventSet, opts: PollOpt) {
+        self.regs.insert(fd,
+                         Registration {
+                             token: token,
+                             opts: opts,
+                             reported: EventSet::empty(),
+                         });
+        self.set_interest(fd, evset);
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Fired {
-    fd: RawFd,
-    evset: EventSet,
-}
+impl Backend for Select {
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()> {
+        let start = Instant::now();
+        let nfired = fired.len();
+        // Edge-triggered descriptors that are still ready from an earlier poll. These are left out
+        // of any retry so that `select` does not keep returning immediately because of them.
+        let mut suppressed = Vec::new();
+        // Oneshot descriptors that fired and must be disarmed.
+        let mut disarm = Vec::new();
+
+        loop {
+            // Clone the `fd_set`s as `select` will modify them.
+            let mut rfds = self.rfds.clone();
+            let mut wfds = self.wfds.clone();
+            for &fd in &suppressed {
+                unsafe {
+                    libc::FD_CLR(fd, &mut rfds);
+                    libc::FD_CLR(fd, &mut wfds);
+                }
+            }
+            let nfds = self.maxfd + 1;
 
-impl Fired {
-    pub fn fd(&self) -> RawFd {
-        self.fd
-    }
+            let remaining = timeout.map(|dur| {
+                dur.checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0))
+            });
 
-    pub fn evset(&self) -> EventSet {
-        self.evset
-    }
-}
+            let nready = try!(select(nfds, &mut rfds, &mut wfds, remaining));
 
-pub struct Iter {
-    maxfd: RawFd,
-    curfd: RawFd,
-    rfds: libc::fd_set,
-    wfds: libc::fd_set,
-}
+            let mut still_ready = Vec::new();
 
-impl Iterator for Iter {
-    type Item = Fired;
+            for fd in 0..nfds {
+                if suppressed.contains(&fd) {
+                    continue;
+                }
+                let reg = match self.regs.get_mut(&fd) {
+                    Some(reg) => reg,
+                    None => continue,
+                };
 
-    fn next(&mut self) -> Option<Fired> {
-        while self.curfd <= self.maxfd {
-            let is_read = unsafe { libc::FD_ISSET(self.curfd, &self.rfds) };
-            let is_write = unsafe { libc::FD_ISSET(self.curfd, &self.wfds) };
+                let mut ready = EventSet::empty();
+                unsafe {
+                    if libc::FD_ISSET(fd, &rfds) {
+                        ready.insert(event::READABLE);
+                    }
+                    if libc::FD_ISSET(fd, &wfds) {
+                        ready.insert(event::WRITABLE);
+                    }
+                }
 
-            if !is_read && !is_write {
-                self.curfd += 1;
-                continue;
-            } else {
-                let mut evset = EventSet::empty();
+                let evset = if reg.opts.is_edge() {
+                    let evset = ready - reg.reported;
+                    reg.reported = ready;
+                    evset
+                } else {
+                    ready
+                };
 
-                if is_read {
-                    evset.insert(event::READABLE);
+                if evset.is_empty() {
+                    if !ready.is_empty() {
+                        still_ready.push(fd);
+                    }
+                    continue;
                 }
-                if is_write {
-                    evset.insert(event::WRITABLE);
+
+                if reg.opts.is_oneshot() {
+                    disarm.push(fd);
                 }
 
-                let fired = Fired {
-                    fd: self.curfd,
+                fired.push(Fired {
+                    token: reg.token,
                     evset: evset,
-                };
+                    timeout: false,
+                });
+            }
 
-                self.curfd += 1;
-                return Some(fired);
+            // Only go around again if everything `select` woke up for was suppressed.
+            if nready == 0 || fired.len() > nfired {
+                break;
             }
+            suppressed.extend(still_ready);
+        }
+
+        for fd in disarm {
+            self.set_interest(fd, EventSet::empty());
+        }
+
+        Ok(())
+    }
+
+    fn register(&mut self, fd: RawFd, token: Token, evset: EventSet, opts: PollOpt) -> Result<()> {
+        // `fd_set` cannot hold descriptors beyond `FD_SETSIZE`.
+        if fd < 0 || fd as usize >= libc::FD_SETSIZE as usize {
+            return Err(Error::from_raw_os_error(libc::EINVAL));
+        }
+        if self.regs.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::EEXIST));
         }
-        None
+
+        self.insert(fd, token, evset, opts);
+        Ok(())
+    }
+
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        if !self.regs.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::ENOENT));
+        }
+
+        self.insert(fd, token, evset, opts);
+        Ok(())
+    }
+
+    fn deregister(&mut self, fd: RawFd) -> Result<()> {
+        if self.regs.remove(&fd).is_none() {
+            return Err(Error::from_raw_os_error(libc::ENOENT));
+        }
+        self.set_interest(fd, EventSet::empty());
+
+        Ok(())
     }
 }
 
-impl fmt::Debug for Iter {
+impl fmt::Debug for Select {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Might as well give some useful debug info.
         let mut rfds = Vec::new();
@@ -250,11 +275,11 @@ impl fmt::Debug for Iter {
             }
         }
 
-        f.debug_struct("Iter")
+        f.debug_struct("Select")
             .field("maxfd", &self.maxfd)
-            .field("curfd", &self.curfd)
             .field("rfds", &rfds)
             .field("wfds", &wfds)
+            .field("regs", &self.regs)
             .finish()
     }
 }
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::os::unix::io::RawFd;
+use std::io::{Result, Error};
+use std::mem;
+use std::time::{Duration, Instant};
+
+use libc;
+
+use event::{self, EventSet, PollOpt};
+use token::Token;
+use uring::sys::{self, Ring, Sqe};
+use super::{Backend, Fired};
+
+const ENTRIES: u32 = 256;
+
+// The user data of poll removals, whose completions are ignored.
+const REMOVE: u64 = !0;
+
+fn interest(evset: EventSet) -> u32 {
+    let mut events = 0;
+
+    if evset.is_readable() {
+        events |= libc::POLLIN;
+    }
+    if evset.is_writable() {
+        events |= libc::POLLOUT;
+    }
+    if evset.is_priority() {
+        events |= libc::POLLPRI;
+    }
+    if evset.is_hup() {
+        events |= libc::POLLRDHUP;
+    }
+
+    events as u32
+}
+
+fn readiness(revents: u32) -> EventSet {
+    let revents = revents as libc::c_short;
+    let mut evset = EventSet::empty();
+
+    if revents & libc::POLLIN != 0 {
+        evset.insert(event::READABLE);
+    }
+    if revents & libc::POLLOUT != 0 {
+        evset.insert(event::WRITABLE);
+    }
+    if revents & libc::POLLPRI != 0 {
+        evset.insert(event::PRIORITY);
+    }
+    if revents & (libc::POLLERR | libc::POLLNVAL) != 0 {
+        evset.insert(event::ERROR);
+    }
+    if revents & (libc::POLLHUP | libc::POLLRDHUP) != 0 {
+        evset.insert(event::HUP);
+    }
+
+    evset
+}
+
+#[derive(Debug)]
+struct Registration {
+    token: Token,
+    evset: EventSet,
+    opts: PollOpt,
+    // The user data of the armed poll, if any.
+    armed: Option<u64>,
+}
+
+/// A set of file descriptors monitored with `IORING_OP_POLL_ADD` on an `io_uring(7)`.
+///
+/// Each registration is a poll submitted to the ring. A level-triggered poll completes once and
+/// is submitted again at the next call to `poll`, an edge-triggered poll is multishot and
+/// completes on each new wakeup of the file descriptor, and a oneshot poll waits to be
+/// re-registered.
+///
+/// An armed poll holds its file open, so file descriptors must be deregistered before they are
+/// closed.
+#[derive(Debug)]
+pub struct Uring {
+    ring: Ring,
+    regs: HashMap<RawFd, Registration>,
+    // The file descriptor of each armed poll, by user data.
+    polls: HashMap<u64, RawFd>,
+    next: u64,
+    // Registrations whose polls have completed and are to be submitted again.
+    rearm: Vec<RawFd>,
+}
+
+impl Uring {
+    pub fn new() -> Result<Uring> {
+        Ok(Uring {
+            ring: try!(Ring::new(ENTRIES)),
+            regs: HashMap::new(),
+            polls: HashMap::new(),
+            next: 0,
+            rearm: Vec::new(),
+        })
+    }
+
+    fn arm(&mut self, fd: RawFd) -> Result<()> {
+        let reg = match self.regs.get_mut(&fd) {
+            Some(reg) => reg,
+            None => return Ok(()),
+        };
+
+        let multishot = reg.opts.is_edge() && !reg.opts.is_oneshot();
+        let user_data = self.next;
+        try!(self.ring.push(Sqe {
+            opcode: sys::IORING_OP_POLL_ADD,
+            fd: fd,
+            len: if multishot { sys::IORING_POLL_ADD_MULTI } else { 0 },
+            op_flags: interest(reg.evset),
+            user_data: user_data,
+            ..Sqe::default()
+        }));
+
+        self.next += 1;
+        reg.armed = Some(user_data);
+        self.polls.insert(user_data, fd);
+        Ok(())
+    }
+
+    fn disarm(&mut self, fd: RawFd) -> Result<()> {
+        let user_data = match self.regs.get_mut(&fd).and_then(|reg| reg.armed.take()) {
+            Some(user_data) => user_data,
+            None => return Ok(()),
+        };
+
+        self.polls.remove(&user_data);
+        self.ring.push(Sqe {
+            opcode: sys::IORING_OP_POLL_REMOVE,
+            fd: -1,
+            addr: user_data,
+            user_data: REMOVE,
+            ..Sqe::default()
+        })
+    }
+}
+
+impl Backend for Uring {
+    fn poll(&mut self, fired: &mut Vec<Fired>, timeout: Option<Duration>) -> Result<()> {
+        let start = Instant::now();
+        let nfired = fired.len();
+
+        loop {
+            for fd in mem::replace(&mut self.rearm, Vec::new()) {
+                if self.regs.get(&fd).and_then(|reg| reg.armed).is_none() {
+                    try!(self.arm(fd));
+                }
+            }
+
+            let remaining = timeout.map(|dur| {
+                dur.checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0))
+            });
+            try!(self.ring.enter(1, remaining));
+
+            let regs = &mut self.regs;
+            let polls = &mut self.polls;
+            let rearm = &mut self.rearm;
+            // Several completions for one file descriptor are merged into one event.
+            let mut index: HashMap<RawFd, usize> = HashMap::new();
+
+            self.ring.complete(|cqe| {
+                let fd = match polls.get(&cqe.user_data) {
+                    Some(&fd) => fd,
+                    None => return,
+                };
+                let reg = match regs.get_mut(&fd) {
+                    Some(reg) => reg,
+                    None => return,
+                };
+
+                if cqe.flags & sys::IORING_CQE_F_MORE == 0 {
+                    polls.remove(&cqe.user_data);
+                    reg.armed = None;
+                    if !reg.opts.is_oneshot() {
+                        rearm.push(fd);
+                    }
+                }
+
+                let evset = if cqe.res < 0 {
+                    EventSet::error()
+                } else {
+                    readiness(cqe.res as u32)
+                };
+                if evset.is_empty() {
+                    return;
+                }
+
+                match index.entry(fd) {
+                    Entry::Occupied(entry) => {
+                        fired[*entry.get()].evset.insert(evset);
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(fired.len());
+                        fired.push(Fired {
+                            token: reg.token,
+                            evset: evset,
+                            timeout: false,
+                        });
+                    }
+                }
+            });
+
+            // Removals and the polls they cancel complete without readiness, and must not end the
+            // wait.
+            let expired = match timeout {
+                Some(dur) => start.elapsed() >= dur,
+                None => false,
+            };
+            if fired.len() > nfired || expired {
+                return Ok(());
+            }
+        }
+    }
+
+    fn register(&mut self, fd: RawFd, token: Token, evset: EventSet, opts: PollOpt) -> Result<()> {
+        if self.regs.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::EEXIST));
+        }
+
+        self.regs.insert(fd,
+                         Registration {
+                             token: token,
+                             evset: evset,
+                             opts: opts,
+                             armed: None,
+                         });
+        let res = self.arm(fd);
+        if res.is_err() {
+            self.regs.remove(&fd);
+        }
+        res
+    }
+
+    fn reregister(&mut self,
+                  fd: RawFd,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        if !self.regs.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::ENOENT));
+        }
+
+        try!(self.disarm(fd));
+        self.regs.insert(fd,
+                         Registration {
+                             token: token,
+                             evset: evset,
+                             opts: opts,
+                             armed: None,
+                         });
+        self.arm(fd)
+    }
+
+    fn deregister(&mut self, fd: RawFd) -> Result<()> {
+        if !self.regs.contains_key(&fd) {
+            return Err(Error::from_raw_os_error(libc::ENOENT));
+        }
+
+        try!(self.disarm(fd));
+        self.regs.remove(&fd);
+
+        // Submit the removal now, so that the poll lets go of the file before it is closed.
+        self.ring.enter(0, None)
+    }
+}
@@ -0,0 +1,383 @@
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::io::{Result, Error};
+use std::mem;
+
+use libc;
+
+/// A source of signal notifications that can be registered with a `Selector`.
+///
+/// On Linux the given signals are blocked for the calling thread and delivered through a
+/// `signalfd`. Threads spawned afterwards inherit the signal mask, so a `Signals` should be created
+/// before any other threads are started. Elsewhere, a handler installed for each signal writes to a
+/// self-pipe; only one `Signals` may exist at a time on these platforms.
+///
+/// The file descriptor becomes readable when a signal is pending, at which point `read` yields the
+/// delivered signals one at a time.
+#[derive(Debug)]
+pub struct Signals {
+    inner: imp::Inner,
+}
+
+impl Signals {
+    /// Creates a `Signals` that receives the given signals.
+    pub fn new(signals: &[libc::c_int]) -> Result<Signals> {
+        Ok(Signals { inner: try!(imp::Inner::new(signals)) })
+    }
+
+    /// Reads the next pending signal, or returns `None` if there are none.
+    pub fn read(&mut self) -> Result<Option<SigInfo>> {
+        self.inner.read()
+    }
+}
+
+impl AsRawFd for Signals {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+/// Information about a delivered signal.
+///
+/// The sending process, user and exit status are only reported on Linux, and are zero elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SigInfo {
+    signo: libc::c_int,
+    code: libc::c_int,
+    pid: libc::pid_t,
+    uid: libc::uid_t,
+    status: libc::c_int,
+}
+
+impl SigInfo {
+    /// The signal number.
+    pub fn signo(&self) -> libc::c_int {
+        self.signo
+    }
+
+    /// The signal code, describing why the signal was sent.
+    pub fn code(&self) -> libc::c_int {
+        self.code
+    }
+
+    /// The process that sent the signal, or the child that changed state for `SIGCHLD`.
+    pub fn pid(&self) -> libc::pid_t {
+        self.pid
+    }
+
+    /// The real user ID of the sending process.
+    pub fn uid(&self) -> libc::uid_t {
+        self.uid
+    }
+
+    /// The exit status or signal of the child for `SIGCHLD`.
+    pub fn status(&self) -> libc::c_int {
+        self.status
+    }
+}
+
+// Builds a `sigset_t` holding the given signals.
+fn sigset(signals: &[libc::c_int]) -> Result<libc::sigset_t> {
+    unsafe {
+        let mut set: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut set);
+
+        for &signo in signals {
+            if libc::sigaddset(&mut set, signo) == -1 {
+                return Err(Error::last_os_error());
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::fmt;
+    use std::os::unix::io::RawFd;
+    use std::io::{Result, Error, ErrorKind};
+    use std::mem;
+    use std::ptr;
+
+    use libc;
+
+    use super::{SigInfo, sigset};
+
+    pub struct Inner {
+        fd: RawFd,
+        // The signals blocked by `new`, which were not already blocked.
+        blocked: libc::sigset_t,
+    }
+
+    impl Inner {
+        pub fn new(signals: &[libc::c_int]) -> Result<Inner> {
+            let set = try!(sigset(signals));
+
+            // Block the signals so that they are only delivered through the signalfd.
+            let mut old: libc::sigset_t = unsafe { mem::zeroed() };
+            let res = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut old) };
+            if res != 0 {
+                return Err(Error::from_raw_os_error(res));
+            }
+
+            let mut blocked = try!(sigset(&[]));
+            for &signo in signals {
+                if unsafe { libc::sigismember(&old, signo) } == 0 {
+                    unsafe { libc::sigaddset(&mut blocked, signo) };
+                }
+            }
+
+            let fd = unsafe { libc::signalfd(-1, &set, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC) };
+            if fd == -1 {
+                let err = Error::last_os_error();
+                unsafe { libc::pthread_sigmask(libc::SIG_UNBLOCK, &blocked, ptr::null_mut()) };
+                return Err(err);
+            }
+
+            Ok(Inner {
+                fd: fd,
+                blocked: blocked,
+            })
+        }
+
+        pub fn as_raw_fd(&self) -> RawFd {
+            self.fd
+        }
+
+        pub fn read(&mut self) -> Result<Option<SigInfo>> {
+            let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+            let size = mem::size_of::<libc::signalfd_siginfo>();
+
+            loop {
+                let res = unsafe {
+                    libc::read(self.fd,
+                               &mut info as *mut libc::signalfd_siginfo as *mut libc::c_void,
+                               size)
+                };
+
+                if res == -1 {
+                    let err = Error::last_os_error();
+                    match err.kind() {
+                        ErrorKind::WouldBlock => return Ok(None),
+                        ErrorKind::Interrupted => continue,
+                        _ => return Err(err),
+                    }
+                }
+
+                return Ok(Some(SigInfo {
+                    signo: info.ssi_signo as libc::c_int,
+                    code: info.ssi_code,
+                    pid: info.ssi_pid as libc::pid_t,
+                    uid: info.ssi_uid as libc::uid_t,
+                    status: info.ssi_status,
+                }));
+            }
+        }
+    }
+
+    impl Drop for Inner {
+        fn drop(&mut self) {
+            // Consume the signals still pending, which would otherwise be delivered with their
+            // default action once unblocked.
+            while let Ok(Some(_)) = self.read() {}
+
+            unsafe {
+                libc::close(self.fd);
+                libc::pthread_sigmask(libc::SIG_UNBLOCK, &self.blocked, ptr::null_mut());
+            }
+        }
+    }
+
+    impl fmt::Debug for Inner {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            fmt.debug_struct("Inner").field("fd", &self.fd).finish()
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::fmt;
+    use std::os::unix::io::RawFd;
+    use std::io::{Result, Error, ErrorKind};
+    use std::sync::atomic::{AtomicIsize, Ordering};
+    use std::mem;
+    use std::ptr;
+
+    use libc;
+
+    use super::{SigInfo, sigset};
+
+    // Write end of the self-pipe, or -1 if no `Signals` exists.
+    static PIPE: AtomicIsize = AtomicIsize::new(-1);
+
+    // What the signal handler writes to the self-pipe for each delivered signal.
+    #[repr(C)]
+    struct Record {
+        signo: libc::c_int,
+        code: libc::c_int,
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    unsafe fn errno() -> *mut libc::c_int {
+        libc::__error()
+    }
+
+    #[cfg(any(target_os = "openbsd",
+              target_os = "netbsd",
+              target_os = "bitrig",
+              target_os = "android"))]
+    unsafe fn errno() -> *mut libc::c_int {
+        libc::__errno()
+    }
+
+    #[cfg(target_os = "dragonfly")]
+    unsafe fn errno() -> *mut libc::c_int {
+        libc::__errno_location()
+    }
+
+    // Only async-signal-safe operations may be performed here, and `errno` must be left as the
+    // interrupted code set it.
+    extern "C" fn handler(signo: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+        let fd = PIPE.load(Ordering::Relaxed);
+        if fd < 0 {
+            return;
+        }
+
+        let code = if info.is_null() {
+            0
+        } else {
+            unsafe { (*info).si_code }
+        };
+        let record = Record {
+            signo: signo,
+            code: code,
+        };
+
+        unsafe {
+            let saved = *errno();
+            libc::write(fd as RawFd,
+                        &record as *const Record as *const libc::c_void,
+                        mem::size_of::<Record>());
+            *errno() = saved;
+        }
+    }
+
+    pub struct Inner {
+        read: RawFd,
+        write: RawFd,
+        // The signals handled, with the actions they had before, to be restored on drop.
+        previous: Vec<(libc::c_int, libc::sigaction)>,
+    }
+
+    impl Inner {
+        pub fn new(signals: &[libc::c_int]) -> Result<Inner> {
+            // Validate the signal numbers up front.
+            try!(sigset(signals));
+
+            let mut fds = [0 as libc::c_int; 2];
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+                return Err(Error::last_os_error());
+            }
+
+            if PIPE.compare_exchange(-1, fds[1] as isize, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err() {
+                unsafe {
+                    libc::close(fds[0]);
+                    libc::close(fds[1]);
+                }
+                return Err(Error::new(ErrorKind::AlreadyExists, "signal pipe already in use"));
+            }
+
+            let mut inner = Inner {
+                read: fds[0],
+                write: fds[1],
+                previous: Vec::new(),
+            };
+            for &fd in &fds {
+                try!(unsafe { ::set_nonblock(fd) });
+                try!(unsafe { ::set_cloexec(fd) });
+            }
+
+            for &signo in signals {
+                let mut previous: libc::sigaction = unsafe { mem::zeroed() };
+                let res = unsafe {
+                    let mut action: libc::sigaction = mem::zeroed();
+                    action.sa_sigaction = handler as libc::sighandler_t;
+                    action.sa_flags = libc::SA_SIGINFO | libc::SA_RESTART;
+                    libc::sigemptyset(&mut action.sa_mask);
+                    libc::sigaction(signo, &action, &mut previous)
+                };
+                if res == -1 {
+                    return Err(Error::last_os_error());
+                }
+                inner.previous.push((signo, previous));
+            }
+
+            Ok(inner)
+        }
+
+        pub fn as_raw_fd(&self) -> RawFd {
+            self.read
+        }
+
+        pub fn read(&mut self) -> Result<Option<SigInfo>> {
+            let mut record = Record {
+                signo: 0,
+                code: 0,
+            };
+
+            loop {
+                let res = unsafe {
+                    libc::read(self.read,
+                               &mut record as *mut Record as *mut libc::c_void,
+                               mem::size_of::<Record>())
+                };
+
+                if res == -1 {
+                    let err = Error::last_os_error();
+                    match err.kind() {
+                        ErrorKind::WouldBlock => return Ok(None),
+                        ErrorKind::Interrupted => continue,
+                        _ => return Err(err),
+                    }
+                }
+
+                return Ok(Some(SigInfo {
+                    signo: record.signo,
+                    code: record.code,
+                    pid: 0,
+                    uid: 0,
+                    status: 0,
+                }));
+            }
+        }
+    }
+
+    impl Drop for Inner {
+        fn drop(&mut self) {
+            unsafe {
+                // In reverse, so a signal listed twice gets back its original action.
+                for &(signo, ref previous) in self.previous.iter().rev() {
+                    libc::sigaction(signo, previous, ptr::null_mut());
+                }
+            }
+            PIPE.store(-1, Ordering::SeqCst);
+            unsafe {
+                libc::close(self.read);
+                libc::close(self.write);
+            }
+        }
+    }
+
+    impl fmt::Debug for Inner {
+        fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+            let signals = self.previous.iter().map(|&(signo, _)| signo).collect::<Vec<_>>();
+            fmt.debug_struct("Inner")
+                .field("read", &self.read)
+                .field("write", &self.write)
+                .field("signals", &signals)
+                .finish()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::cmp;
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::io::{Result, Error, ErrorKind};
+use std::time::Duration;
+use std::mem;
+use std::ptr;
+
+use libc;
+
+fn timespec(dur: Duration) -> libc::timespec {
+    libc::timespec {
+        tv_sec: cmp::min(dur.as_secs(), libc::time_t::max_value() as u64) as libc::time_t,
+        tv_nsec: dur.subsec_nanos() as libc::c_long,
+    }
+}
+
+/// A timer backed by a `timerfd`, which can be registered with a `Selector`.
+///
+/// The timer's file descriptor becomes readable each time it expires. Expirations accumulate until
+/// they are consumed with `read`, so ticks missed while the owner was busy are not lost.
+#[derive(Debug)]
+pub struct Timer {
+    fd: RawFd,
+}
+
+impl Timer {
+    /// Creates a disarmed `Timer` on the monotonic clock.
+    pub fn new() -> Result<Timer> {
+        let fd = unsafe {
+            libc::timerfd_create(libc::CLOCK_MONOTONIC,
+                                 libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+        };
+
+        if fd == -1 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(Timer { fd: fd })
+        }
+    }
+
+    /// Arms the timer to expire once, after `after` has elapsed.
+    ///
+    /// Any previous arming is replaced.
+    pub fn set_timeout(&mut self, after: Duration) -> Result<()> {
+        self.arm(after, Duration::from_secs(0))
+    }
+
+    /// Arms the timer to expire every `interval`, starting one `interval` from now.
+    ///
+    /// Any previous arming is replaced.
+    pub fn set_interval(&mut self, interval: Duration) -> Result<()> {
+        self.set_interval_after(interval, interval)
+    }
+
+    /// Arms the timer to expire first after `after` and then every `interval`.
+    ///
+    /// Any previous arming is replaced.
+    pub fn set_interval_after(&mut self, after: Duration, interval: Duration) -> Result<()> {
+        self.arm(after, interval)
+    }
+
+    /// Disarms the timer, discarding any pending expirations.
+    pub fn cancel(&mut self) -> Result<()> {
+        let spec: libc::itimerspec = unsafe { mem::zeroed() };
+        self.settime(&spec)
+    }
+
+    /// Returns the number of times the timer has expired since it was last read, resetting the
+    /// count to zero.
+    pub fn read(&mut self) -> Result<u64> {
+        let mut count: u64 = 0;
+        let res = unsafe {
+            libc::read(self.fd,
+                       &mut count as *mut u64 as *mut libc::c_void,
+                       mem::size_of::<u64>())
+        };
+
+        if res == -1 {
+            let err = Error::last_os_error();
+            if err.kind() == ErrorKind::WouldBlock {
+                Ok(0)
+            } else {
+                Err(err)
+            }
+        } else {
+            Ok(count)
+        }
+    }
+
+    fn arm(&mut self, after: Duration, interval: Duration) -> Result<()> {
+        // A zero initial expiration disarms a timerfd, so expire as soon as possible instead.
+        let after = if after == Duration::from_secs(0) {
+            Duration::new(0, 1)
+        } else {
+            after
+        };
+
+        let spec = libc::itimerspec {
+            it_interval: timespec(interval),
+            it_value: timespec(after),
+        };
+
+        self.settime(&spec)
+    }
+
+    fn settime(&mut self, spec: &libc::itimerspec) -> Result<()> {
+        let res = unsafe { libc::timerfd_settime(self.fd, 0, spec, ptr::null_mut()) };
+
+        if res == -1 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl AsRawFd for Timer {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
@@ -0,0 +1,18 @@
+/// An identifier associated with a file descriptor registered with a `Selector`.
+///
+/// The `Token` given at registration is handed back on every `Fired` event for that registration,
+/// allowing callers to map readiness notifications onto their own data structures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(pub usize);
+
+impl From<usize> for Token {
+    fn from(val: usize) -> Token {
+        Token(val)
+    }
+}
+
+impl From<Token> for usize {
+    fn from(token: Token) -> usize {
+        token.0
+    }
+}
@@ -0,0 +1,240 @@
+//! Completion-based I/O with `io_uring(7)`.
+//!
+//! Where a `Selector` reports that a file descriptor is ready and leaves the I/O to the caller, a
+//! `Uring` performs the I/O itself and reports when it has completed. Requires Linux 5.13 or
+//! later and the `io_uring` feature.
+
+use std::fmt;
+use std::io::{Result, Error};
+use std::mem;
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::time::Duration;
+
+use libc;
+
+use token::Token;
+
+pub(crate) mod sys;
+
+use self::sys::{Ring, Sqe};
+
+const DEFAULT_ENTRIES: u32 = 256;
+
+/// The result of an operation submitted to a `Uring`.
+#[derive(Debug)]
+pub enum Completion {
+    /// A read, with the buffer truncated to the bytes read. An empty buffer is end-of-file.
+    Read(Token, Result<Vec<u8>>),
+    /// A write, with the number of bytes written and the buffer given to `write`.
+    Write(Token, Result<usize>, Vec<u8>),
+    /// An accept, with the accepted connection. The descriptor is nonblocking and close-on-exec,
+    /// and is owned by the caller.
+    Accept(Token, Result<RawFd>),
+}
+
+impl Completion {
+    /// Returns the token the operation was submitted with.
+    pub fn token(&self) -> Token {
+        match *self {
+            Completion::Read(token, _) |
+            Completion::Write(token, _, _) |
+            Completion::Accept(token, _) => token,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Kind {
+    Read,
+    Write,
+    Accept,
+}
+
+// An operation in flight, holding the buffer the kernel reads or writes until it completes.
+#[derive(Debug)]
+struct Op {
+    token: Token,
+    kind: Kind,
+    buf: Vec<u8>,
+}
+
+/// Submits reads, writes and accepts to the kernel, which performs them asynchronously.
+///
+/// Operations are queued by `read`, `write` and `accept`, and submitted by `complete`, which
+/// also collects the operations that have finished. Each operation owns its buffer until it
+/// completes. Buffers of operations still in flight when the `Uring` is dropped are leaked, as
+/// the kernel may yet access them.
+pub struct Uring {
+    ring: Ring,
+    // Operations in flight, indexed by the user data of their submissions.
+    ops: Vec<Option<Op>>,
+    free: Vec<usize>,
+    in_flight: usize,
+}
+
+impl Uring {
+    /// Creates a `Uring` with room to queue 256 submissions.
+    ///
+    /// Fails if `io_uring` is unavailable or not permitted.
+    pub fn new() -> Result<Uring> {
+        Uring::with_entries(DEFAULT_ENTRIES)
+    }
+
+    /// Creates a `Uring` with room to queue `entries` submissions, rounded up to a power of two.
+    pub fn with_entries(entries: u32) -> Result<Uring> {
+        Ok(Uring {
+            ring: try!(Ring::new(entries)),
+            ops: Vec::new(),
+            free: Vec::new(),
+            in_flight: 0,
+        })
+    }
+
+    /// Queues a read of up to `len` bytes from `fd`, at its current position if it has one.
+    pub fn read(&mut self, fd: RawFd, len: usize, token: Token) -> Result<()> {
+        let mut buf = Vec::with_capacity(len);
+        let sqe = Sqe {
+            opcode: sys::IORING_OP_READ,
+            fd: fd,
+            off: !0,
+            addr: buf.as_mut_ptr() as u64,
+            len: sqe_len(len),
+            ..Sqe::default()
+        };
+
+        self.submit(sqe, token, Kind::Read, buf)
+    }
+
+    /// Queues a write of `buf` to `fd`, at its current position if it has one.
+    pub fn write(&mut self, fd: RawFd, buf: Vec<u8>, token: Token) -> Result<()> {
+        let sqe = Sqe {
+            opcode: sys::IORING_OP_WRITE,
+            fd: fd,
+            off: !0,
+            addr: buf.as_ptr() as u64,
+            len: sqe_len(buf.len()),
+            ..Sqe::default()
+        };
+
+        self.submit(sqe, token, Kind::Write, buf)
+    }
+
+    /// Queues an accept of a connection on the listening socket `fd`.
+    pub fn accept(&mut self, fd: RawFd, token: Token) -> Result<()> {
+        let sqe = Sqe {
+            opcode: sys::IORING_OP_ACCEPT,
+            fd: fd,
+            op_flags: (libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC) as u32,
+            ..Sqe::default()
+        };
+
+        self.submit(sqe, token, Kind::Accept, Vec::new())
+    }
+
+    /// Returns the number of operations queued or in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Submits the queued operations and waits up to `timeout` for at least one to complete,
+    /// appending each completed operation to `completions`, and returning how many there were.
+    ///
+    /// A `timeout` of `None` waits indefinitely. Returns immediately if no operations are in
+    /// flight.
+    pub fn complete(&mut self,
+                    completions: &mut Vec<Completion>,
+                    timeout: Option<Duration>)
+                    -> Result<usize> {
+        let min_complete = if self.in_flight > 0 { 1 } else { 0 };
+        try!(self.ring.enter(min_complete, timeout));
+
+        let ops = &mut self.ops;
+        let free = &mut self.free;
+        let mut n = 0;
+        self.ring.complete(|cqe| {
+            let index = cqe.user_data as usize;
+            let op = match ops.get_mut(index).and_then(Option::take) {
+                Some(op) => op,
+                None => return,
+            };
+            free.push(index);
+            n += 1;
+
+            let res = if cqe.res < 0 {
+                Err(Error::from_raw_os_error(-cqe.res))
+            } else {
+                Ok(cqe.res as usize)
+            };
+            completions.push(match op.kind {
+                Kind::Read => {
+                    let mut buf = op.buf;
+                    Completion::Read(op.token, res.map(|len| {
+                        unsafe { buf.set_len(len) };
+                        buf
+                    }))
+                }
+                Kind::Write => Completion::Write(op.token, res, op.buf),
+                Kind::Accept => Completion::Accept(op.token, res.map(|fd| fd as RawFd)),
+            });
+        });
+
+        self.in_flight -= n;
+        Ok(n)
+    }
+
+    fn submit(&mut self, mut sqe: Sqe, token: Token, kind: Kind, buf: Vec<u8>) -> Result<()> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.ops.push(None);
+                self.ops.len() - 1
+            }
+        };
+
+        sqe.user_data = index as u64;
+        if let Err(err) = self.ring.push(sqe) {
+            self.free.push(index);
+            return Err(err);
+        }
+
+        self.ops[index] = Some(Op {
+            token: token,
+            kind: kind,
+            buf: buf,
+        });
+        self.in_flight += 1;
+        Ok(())
+    }
+}
+
+impl AsRawFd for Uring {
+    fn as_raw_fd(&self) -> RawFd {
+        self.ring.as_raw_fd()
+    }
+}
+
+impl Drop for Uring {
+    fn drop(&mut self) {
+        for op in self.ops.drain(..).flatten() {
+            mem::forget(op.buf);
+        }
+    }
+}
+
+impl fmt::Debug for Uring {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Uring")
+            .field("fd", &self.ring.as_raw_fd())
+            .field("in_flight", &self.in_flight)
+            .finish()
+    }
+}
+
+// Clamps a buffer length to the 32 bits of a submission.
+fn sqe_len(len: usize) -> u32 {
+    if len > u32::max_value() as usize {
+        u32::max_value()
+    } else {
+        len as u32
+    }
+}
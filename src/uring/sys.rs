@@ -0,0 +1,338 @@
+use std::cmp;
+use std::io::{Result, Error, ErrorKind};
+use std::mem;
+use std::os::unix::io::{RawFd, AsRawFd};
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use libc::{self, c_void};
+
+pub const IORING_OP_POLL_ADD: u8 = 6;
+pub const IORING_OP_POLL_REMOVE: u8 = 7;
+pub const IORING_OP_ACCEPT: u8 = 13;
+pub const IORING_OP_READ: u8 = 22;
+pub const IORING_OP_WRITE: u8 = 23;
+
+// Passed in `len` of a poll to keep it armed after each completion.
+pub const IORING_POLL_ADD_MULTI: u32 = 1;
+
+// Set on the completion of a multishot poll that remains armed.
+pub const IORING_CQE_F_MORE: u32 = 1 << 1;
+
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_SQES: libc::off_t = 0x1000_0000;
+
+const IORING_SETUP_CLAMP: u32 = 1 << 4;
+
+const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+const IORING_FEAT_NODROP: u32 = 1 << 1;
+const IORING_FEAT_EXT_ARG: u32 = 1 << 8;
+const IORING_FEAT_RSRC_TAGS: u32 = 1 << 10;
+
+const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+const IORING_ENTER_EXT_ARG: u32 = 1 << 3;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+/// A submission queue entry.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sqe {
+    pub opcode: u8,
+    pub flags: u8,
+    pub ioprio: u16,
+    pub fd: i32,
+    pub off: u64,
+    pub addr: u64,
+    pub len: u32,
+    // The per-opcode flags, such as the events of a poll or the flags of an accept.
+    pub op_flags: u32,
+    pub user_data: u64,
+    pub buf_index: u16,
+    pub personality: u16,
+    pub splice_fd_in: i32,
+    pub addr3: u64,
+    pub pad: u64,
+}
+
+/// A completion queue entry.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Cqe {
+    pub user_data: u64,
+    pub res: i32,
+    pub flags: u32,
+}
+
+#[repr(C)]
+struct GeteventsArg {
+    sigmask: u64,
+    sigmask_sz: u32,
+    pad: u32,
+    ts: u64,
+}
+
+// A region mapped from the ring's file descriptor.
+#[derive(Debug)]
+struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> Result<Mmap> {
+        let ptr = unsafe {
+            libc::mmap(ptr::null_mut(),
+                       len,
+                       libc::PROT_READ | libc::PROT_WRITE,
+                       libc::MAP_SHARED | libc::MAP_POPULATE,
+                       fd,
+                       offset)
+        };
+
+        if ptr == libc::MAP_FAILED {
+            Err(Error::last_os_error())
+        } else {
+            Ok(Mmap { ptr: ptr, len: len })
+        }
+    }
+
+    // Returns a pointer `offset` bytes into the region.
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { (self.ptr as *mut u8).offset(offset as isize) as *mut T }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        let _ = unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// An `io_uring(7)` instance: a submission and a completion queue shared with the kernel.
+///
+/// Requires the features of Linux 5.13 or later, so that the timeouts of `enter` and multishot
+/// polls are available.
+#[derive(Debug)]
+pub struct Ring {
+    fd: RawFd,
+    // Holds both the submission and completion rings, and is only kept to be unmapped.
+    _rings: Mmap,
+    sqes: Mmap,
+    sq_head: *const AtomicU32,
+    sq_tail: *const AtomicU32,
+    sq_mask: u32,
+    sq_entries: u32,
+    sq_array: *mut u32,
+    cq_head: *const AtomicU32,
+    cq_tail: *const AtomicU32,
+    cq_mask: u32,
+    cqes: *const Cqe,
+}
+
+// The ring is only reached through `&mut self`, so it may move between threads.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    /// Sets up a ring with room for `entries` submissions, rounded up to a power of two.
+    pub fn new(entries: u32) -> Result<Ring> {
+        let mut params = Params { flags: IORING_SETUP_CLAMP, ..Params::default() };
+
+        let fd = unsafe {
+            libc::syscall(libc::SYS_io_uring_setup,
+                          cmp::max(entries, 1),
+                          &mut params as *mut Params)
+        };
+        if fd == -1 {
+            return Err(Error::last_os_error());
+        }
+        let fd = fd as RawFd;
+
+        // Multishot polls have no feature flag of their own, but arrived in 5.13 alongside
+        // resource tags.
+        let required = IORING_FEAT_SINGLE_MMAP | IORING_FEAT_NODROP | IORING_FEAT_EXT_ARG |
+                       IORING_FEAT_RSRC_TAGS;
+        if params.features & required != required {
+            let _ = unsafe { libc::close(fd) };
+            return Err(Error::new(ErrorKind::Other, "io_uring lacks required features"));
+        }
+
+        match Ring::map(fd, &params) {
+            Ok(ring) => Ok(ring),
+            Err(err) => {
+                let _ = unsafe { libc::close(fd) };
+                Err(err)
+            }
+        }
+    }
+
+    fn map(fd: RawFd, params: &Params) -> Result<Ring> {
+        let sq_off = &params.sq_off;
+        let cq_off = &params.cq_off;
+
+        let sq_len = sq_off.array as usize + params.sq_entries as usize * mem::size_of::<u32>();
+        let cq_len = cq_off.cqes as usize + params.cq_entries as usize * mem::size_of::<Cqe>();
+        let rings = try!(Mmap::new(fd, cmp::max(sq_len, cq_len), IORING_OFF_SQ_RING));
+        let sqes = try!(Mmap::new(fd,
+                                  params.sq_entries as usize * mem::size_of::<Sqe>(),
+                                  IORING_OFF_SQES));
+
+        unsafe {
+            Ok(Ring {
+                fd: fd,
+                sq_head: rings.at(sq_off.head),
+                sq_tail: rings.at(sq_off.tail),
+                sq_mask: *rings.at::<u32>(sq_off.ring_mask),
+                sq_entries: *rings.at::<u32>(sq_off.ring_entries),
+                sq_array: rings.at(sq_off.array),
+                cq_head: rings.at(cq_off.head),
+                cq_tail: rings.at(cq_off.tail),
+                cq_mask: *rings.at::<u32>(cq_off.ring_mask),
+                cqes: rings.at(cq_off.cqes),
+                _rings: rings,
+                sqes: sqes,
+            })
+        }
+    }
+
+    /// Queues `sqe` for submission, first submitting what is queued if the queue is full.
+    pub fn push(&mut self, sqe: Sqe) -> Result<()> {
+        if self.unsubmitted() == self.sq_entries {
+            try!(self.enter(0, None));
+            if self.unsubmitted() == self.sq_entries {
+                return Err(Error::new(ErrorKind::WouldBlock, "io_uring submission queue full"));
+            }
+        }
+
+        unsafe {
+            let tail = (*self.sq_tail).load(Ordering::Relaxed);
+            let index = tail & self.sq_mask;
+            *self.sqes.at::<Sqe>(index * mem::size_of::<Sqe>() as u32) = sqe;
+            *self.sq_array.offset(index as isize) = index;
+            (*self.sq_tail).store(tail.wrapping_add(1), Ordering::Release);
+        }
+
+        Ok(())
+    }
+
+    // Returns the number of queued submissions the kernel has yet to consume.
+    fn unsubmitted(&self) -> u32 {
+        unsafe {
+            let head = (*self.sq_head).load(Ordering::Acquire);
+            (*self.sq_tail).load(Ordering::Relaxed).wrapping_sub(head)
+        }
+    }
+
+    /// Submits queued entries, then waits up to `timeout` for at least `min_complete`
+    /// completions. A `timeout` of `None` waits indefinitely.
+    ///
+    /// Expiry of the timeout is not an error.
+    pub fn enter(&mut self, min_complete: u32, timeout: Option<Duration>) -> Result<()> {
+        let ts = timeout.map(|dur| {
+            libc::timespec {
+                tv_sec: cmp::min(dur.as_secs(), libc::time_t::max_value() as u64) as libc::time_t,
+                tv_nsec: dur.subsec_nanos() as libc::c_long,
+            }
+        });
+        let arg = GeteventsArg {
+            sigmask: 0,
+            sigmask_sz: 0,
+            pad: 0,
+            ts: ts.as_ref().map_or(0, |ts| ts as *const libc::timespec as u64),
+        };
+
+        let mut flags = IORING_ENTER_EXT_ARG;
+        if min_complete > 0 {
+            flags |= IORING_ENTER_GETEVENTS;
+        }
+
+        let res = unsafe {
+            libc::syscall(libc::SYS_io_uring_enter,
+                          self.fd,
+                          self.unsubmitted(),
+                          min_complete,
+                          flags,
+                          &arg as *const GeteventsArg,
+                          mem::size_of::<GeteventsArg>())
+        };
+        if res == -1 {
+            let err = Error::last_os_error();
+            // `ETIME` is the timeout expiring, and `EBUSY` completions awaiting reaping.
+            match err.raw_os_error() {
+                Some(libc::ETIME) | Some(libc::EBUSY) => {}
+                _ => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calls `f` for, and removes, each completion waiting in the queue.
+    pub fn complete<F: FnMut(&Cqe)>(&mut self, mut f: F) {
+        unsafe {
+            let mut head = (*self.cq_head).load(Ordering::Relaxed);
+            let tail = (*self.cq_tail).load(Ordering::Acquire);
+
+            while head != tail {
+                f(&*self.cqes.offset((head & self.cq_mask) as isize));
+                head = head.wrapping_add(1);
+            }
+
+            (*self.cq_head).store(head, Ordering::Release);
+        }
+    }
+}
+
+impl AsRawFd for Ring {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        let _ = unsafe { libc::close(self.fd) };
+    }
+}
@@ -0,0 +1,226 @@
+use std::io::Result;
+use std::sync::Arc;
+use std::usize;
+
+use event::{EventSet, PollOpt};
+use selector::Selector;
+use token::Token;
+
+/// The `Token` reserved for the `Waker` of a `Selector`.
+pub const TOKEN: Token = Token(usize::MAX);
+
+/// Wakes a thread blocked in `Selector::poll` from any other thread.
+///
+/// A `Waker` is bound to the `Selector` it was created with. Calling `wake` causes that
+/// `Selector` to return a `Fired` event for which `is_wakeup` returns `true`. Wakeups are
+/// coalesced, so several calls to `wake` between polls yield a single event.
+///
+/// `Token(usize::MAX)` is reserved for the `Waker` and must not be used for other registrations.
+#[derive(Debug, Clone)]
+pub struct Waker {
+    inner: Arc<Inner>,
+}
+
+impl Waker {
+    /// Creates a `Waker` for the given `Selector`.
+    ///
+    /// A `Selector` has at most one underlying waker; subsequent calls return handles to it.
+    pub fn new(selector: &mut Selector) -> Result<Waker> {
+        if let Some(inner) = selector.waker() {
+            return Ok(Waker { inner: inner.clone() });
+        }
+
+        let inner = Arc::new(try!(Inner::new()));
+        try!(selector.register(inner.as_raw_fd(),
+                               TOKEN,
+                               EventSet::readable(),
+                               PollOpt::level()));
+        selector.set_waker(inner.clone());
+
+        Ok(Waker { inner: inner })
+    }
+
+    /// Wakes the associated `Selector`.
+    pub fn wake(&self) -> Result<()> {
+        self.inner.wake()
+    }
+}
+
+#[cfg(all(not(feature = "select"),
+          target_os = "linux"))]
+pub use self::eventfd::Inner;
+
+#[cfg(not(all(not(feature = "select"),
+              target_os = "linux")))]
+pub use self::pipe::Inner;
+
+#[cfg(all(not(feature = "select"),
+          target_os = "linux"))]
+mod eventfd {
+    use std::os::unix::io::RawFd;
+    use std::io::{Result, Error, ErrorKind};
+    use std::mem;
+
+    use libc;
+
+    // An eventfd counter. Writing increments the counter, reading resets it to zero.
+    #[derive(Debug)]
+    pub struct Inner {
+        fd: RawFd,
+    }
+
+    impl Inner {
+        pub fn new() -> Result<Inner> {
+            let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+
+            if fd == -1 {
+                Err(Error::last_os_error())
+            } else {
+                Ok(Inner { fd: fd })
+            }
+        }
+
+        pub fn as_raw_fd(&self) -> RawFd {
+            self.fd
+        }
+
+        pub fn wake(&self) -> Result<()> {
+            let buf: u64 = 1;
+            let res = unsafe {
+                libc::write(self.fd,
+                            &buf as *const u64 as *const libc::c_void,
+                            mem::size_of::<u64>())
+            };
+
+            if res == -1 {
+                let err = Error::last_os_error();
+                // The counter is saturated, so a wakeup is already pending.
+                if err.kind() == ErrorKind::WouldBlock {
+                    Ok(())
+                } else {
+                    Err(err)
+                }
+            } else {
+                Ok(())
+            }
+        }
+
+        pub fn reset(&self) -> Result<()> {
+            let mut buf: u64 = 0;
+            let res = unsafe {
+                libc::read(self.fd,
+                           &mut buf as *mut u64 as *mut libc::c_void,
+                           mem::size_of::<u64>())
+            };
+
+            if res == -1 {
+                let err = Error::last_os_error();
+                if err.kind() == ErrorKind::WouldBlock {
+                    Ok(())
+                } else {
+                    Err(err)
+                }
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Drop for Inner {
+        fn drop(&mut self) {
+            let _ = unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(not(all(not(feature = "select"),
+              target_os = "linux")))]
+mod pipe {
+    use std::os::unix::io::RawFd;
+    use std::io::{Result, Error, ErrorKind};
+
+    use libc;
+
+    // A self-pipe. Writing a byte signals the read end, which is drained on reset.
+    #[derive(Debug)]
+    pub struct Inner {
+        read: RawFd,
+        write: RawFd,
+    }
+
+    impl Inner {
+        pub fn new() -> Result<Inner> {
+            let mut fds = [0 as libc::c_int; 2];
+            let res = unsafe { libc::pipe(fds.as_mut_ptr()) };
+            if res == -1 {
+                return Err(Error::last_os_error());
+            }
+
+            let inner = Inner {
+                read: fds[0],
+                write: fds[1],
+            };
+            for &fd in &fds {
+                try!(unsafe { ::set_nonblock(fd) });
+                try!(unsafe { ::set_cloexec(fd) });
+            }
+
+            Ok(inner)
+        }
+
+        pub fn as_raw_fd(&self) -> RawFd {
+            self.read
+        }
+
+        pub fn wake(&self) -> Result<()> {
+            let buf = [1u8];
+            let res = unsafe { libc::write(self.write, buf.as_ptr() as *const libc::c_void, 1) };
+
+            if res == -1 {
+                let err = Error::last_os_error();
+                // The pipe is full, so a wakeup is already pending.
+                if err.kind() == ErrorKind::WouldBlock {
+                    Ok(())
+                } else {
+                    Err(err)
+                }
+            } else {
+                Ok(())
+            }
+        }
+
+        pub fn reset(&self) -> Result<()> {
+            let mut buf = [0u8; 128];
+
+            loop {
+                let res = unsafe {
+                    libc::read(self.read,
+                               buf.as_mut_ptr() as *mut libc::c_void,
+                               buf.len())
+                };
+
+                match res {
+                    -1 => {
+                        let err = Error::last_os_error();
+                        match err.kind() {
+                            ErrorKind::WouldBlock => return Ok(()),
+                            ErrorKind::Interrupted => {}
+                            _ => return Err(err),
+                        }
+                    }
+                    0 => return Ok(()),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    impl Drop for Inner {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.read);
+                libc::close(self.write);
+            }
+        }
+    }
+}
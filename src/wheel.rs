@@ -0,0 +1,272 @@
+use std::time::{Duration, Instant};
+use std::u64;
+
+use token::Token;
+
+const DEFAULT_TICK_MS: u64 = 1;
+const DEFAULT_SLOTS: usize = 256;
+
+/// A handle to a timeout scheduled on a `Selector`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeout {
+    index: usize,
+    version: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    token: Token,
+    // Tick at which the entry expires.
+    deadline: u64,
+    // Incremented each time the entry is freed, invalidating outstanding `Timeout`s.
+    version: u64,
+    linked: bool,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+/// A hashed timing wheel.
+///
+/// Time is divided into ticks of a fixed duration. Each pending timeout lives in the slot for its
+/// deadline tick modulo the number of slots, in a doubly-linked list threaded through a slab of
+/// entries, so inserting, cancelling and resetting a timeout are all O(1).
+#[derive(Debug)]
+pub struct Wheel {
+    tick: Duration,
+    start: Instant,
+    // The next tick to be processed.
+    current: u64,
+    // Head of the list of entries in each slot. The number of slots is a power of two.
+    slots: Vec<Option<usize>>,
+    entries: Vec<Entry>,
+    // Entries that are not in use.
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl Wheel {
+    pub fn new() -> Wheel {
+        Wheel::with_tick(Duration::from_millis(DEFAULT_TICK_MS), DEFAULT_SLOTS)
+    }
+
+    pub fn with_tick(tick: Duration, slots: usize) -> Wheel {
+        Wheel {
+            tick: tick,
+            start: Instant::now(),
+            current: 0,
+            slots: vec![None; slots.next_power_of_two()],
+            entries: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Schedules `token` to expire once `delay` has elapsed.
+    pub fn insert(&mut self, token: Token, delay: Duration) -> Timeout {
+        let deadline = self.deadline(delay);
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.token = token;
+                index
+            }
+            None => {
+                self.entries.push(Entry {
+                    token: token,
+                    deadline: 0,
+                    version: 0,
+                    linked: false,
+                    prev: None,
+                    next: None,
+                });
+                self.entries.len() - 1
+            }
+        };
+
+        self.link(index, deadline);
+        self.len += 1;
+
+        Timeout {
+            index: index,
+            version: self.entries[index].version,
+        }
+    }
+
+    /// Cancels a pending timeout, returning `false` if it already expired or was cancelled.
+    pub fn cancel(&mut self, timeout: Timeout) -> bool {
+        if !self.is_pending(timeout) {
+            return false;
+        }
+
+        self.unlink(timeout.index);
+        self.release(timeout.index);
+
+        true
+    }
+
+    /// Reschedules a pending timeout to expire once `delay` has elapsed, returning `false` if it
+    /// already expired or was cancelled.
+    pub fn reset(&mut self, timeout: Timeout, delay: Duration) -> bool {
+        if !self.is_pending(timeout) {
+            return false;
+        }
+
+        let deadline = self.deadline(delay);
+        self.unlink(timeout.index);
+        self.link(timeout.index, deadline);
+
+        true
+    }
+
+    /// Returns an instant no later than the earliest pending deadline.
+    ///
+    /// This is the start of the next tick whose slot holds any entries. Those entries may belong
+    /// to a later rotation of the wheel, in which case the caller simply wakes up early.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mask = self.slots.len() as u64 - 1;
+        for offset in 0..self.slots.len() as u64 {
+            let tick = self.current + offset;
+            if self.slots[(tick & mask) as usize].is_some() {
+                return Some(self.instant(tick));
+            }
+        }
+
+        None
+    }
+
+    /// Removes every entry whose deadline has passed as of `now`, passing its token to `expired`.
+    pub fn expire<F>(&mut self, now: Instant, mut expired: F)
+        where F: FnMut(Token)
+    {
+        let target = self.ticks(now);
+        if target < self.current {
+            return;
+        }
+
+        // Each slot needs visiting at most once, however far the wheel has to advance.
+        let nslots = self.slots.len() as u64;
+        let steps = if target - self.current >= nslots {
+            nslots
+        } else {
+            target - self.current + 1
+        };
+
+        let mask = nslots - 1;
+        for offset in 0..steps {
+            if self.len == 0 {
+                break;
+            }
+
+            let slot = ((self.current + offset) & mask) as usize;
+            let mut cursor = self.slots[slot];
+            while let Some(index) = cursor {
+                cursor = self.entries[index].next;
+
+                if self.entries[index].deadline <= target {
+                    let token = self.entries[index].token;
+                    self.unlink(index);
+                    self.release(index);
+                    expired(token);
+                }
+            }
+        }
+
+        self.current = target + 1;
+    }
+
+    fn is_pending(&self, timeout: Timeout) -> bool {
+        match self.entries.get(timeout.index) {
+            Some(entry) => entry.linked && entry.version == timeout.version,
+            None => false,
+        }
+    }
+
+    // Returns the deadline tick for a timeout of `delay` from now, rounding up so that a timeout
+    // never fires early.
+    fn deadline(&self, delay: Duration) -> u64 {
+        let elapsed = self.start.elapsed().checked_add(delay).unwrap_or(Duration::new(u64::MAX, 0));
+        let nanos = as_nanos(elapsed);
+        let tick = as_nanos(self.tick);
+
+        let deadline = nanos / tick + (nanos % tick != 0) as u64;
+        // Slots for ticks before `current` have already been visited.
+        if deadline < self.current {
+            self.current
+        } else {
+            deadline
+        }
+    }
+
+    // Returns the number of whole ticks elapsed at `now`.
+    fn ticks(&self, now: Instant) -> u64 {
+        if now < self.start {
+            return 0;
+        }
+        as_nanos(now - self.start) / as_nanos(self.tick)
+    }
+
+    fn instant(&self, tick: u64) -> Instant {
+        let nanos = tick.saturating_mul(as_nanos(self.tick));
+        let dur = Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32);
+
+        self.start.checked_add(dur).unwrap_or(self.start)
+    }
+
+    fn link(&mut self, index: usize, deadline: u64) {
+        let slot = (deadline & (self.slots.len() as u64 - 1)) as usize;
+        let head = self.slots[slot];
+
+        {
+            let entry = &mut self.entries[index];
+            entry.deadline = deadline;
+            entry.linked = true;
+            entry.prev = None;
+            entry.next = head;
+        }
+        if let Some(head) = head {
+            self.entries[head].prev = Some(index);
+        }
+        self.slots[slot] = Some(index);
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next, deadline) = {
+            let entry = &mut self.entries[index];
+            entry.linked = false;
+            (entry.prev.take(), entry.next.take(), entry.deadline)
+        };
+
+        match prev {
+            Some(prev) => self.entries[prev].next = next,
+            None => {
+                let slot = (deadline & (self.slots.len() as u64 - 1)) as usize;
+                self.slots[slot] = next;
+            }
+        }
+        if let Some(next) = next {
+            self.entries[next].prev = prev;
+        }
+    }
+
+    fn release(&mut self, index: usize) {
+        self.entries[index].version += 1;
+        self.free.push(index);
+        self.len -= 1;
+    }
+}
+
+// Durations are saturated to `u64::MAX` nanoseconds, a little over 584 years.
+fn as_nanos(dur: Duration) -> u64 {
+    dur.as_secs()
+        .saturating_mul(1_000_000_000)
+        .saturating_add(dur.subsec_nanos() as u64)
+}
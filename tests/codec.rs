@@ -0,0 +1,170 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token};
+use rivet::codec::{Decoder, Encoder, Framed, LineCodec, LengthCodec, NetstringCodec};
+use rivet::io::Status;
+use rivet::net::UnixStream;
+
+fn encode<C: Encoder<Item = [u8]>>(codec: &mut C, msg: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    codec.encode(msg, &mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_line() {
+    let mut codec = LineCodec::new();
+
+    assert_eq!(encode(&mut codec, b"hello"), b"hello\n");
+    assert_eq!(codec.decode(b"hel").unwrap(), None);
+    assert_eq!(codec.decode(b"hello\r\nworld").unwrap(), Some((b"hello".to_vec(), 7)));
+    assert_eq!(codec.decode(b"\n").unwrap(), Some((vec![], 1)));
+
+    let err = codec.encode(b"two\nlines", &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_length() {
+    let mut codec = LengthCodec::u16_be();
+    assert_eq!(encode(&mut codec, b"abc"), b"\x00\x03abc");
+    assert_eq!(codec.decode(b"\x00").unwrap(), None);
+    assert_eq!(codec.decode(b"\x00\x03ab").unwrap(), None);
+    assert_eq!(codec.decode(b"\x00\x03abcd").unwrap(), Some((b"abc".to_vec(), 5)));
+    let err = codec.encode(&vec![0; 1 << 16], &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+    let mut codec = LengthCodec::u16_le();
+    assert_eq!(&encode(&mut codec, &[7; 258])[..4], b"\x02\x01\x07\x07");
+
+    let mut codec = LengthCodec::u32_be();
+    assert_eq!(encode(&mut codec, b"xy"), b"\x00\x00\x00\x02xy");
+
+    let mut codec = LengthCodec::u32_le();
+    assert_eq!(encode(&mut codec, b"xy"), b"\x02\x00\x00\x00xy");
+    assert_eq!(codec.decode(b"\x02\x00\x00\x00xy").unwrap(), Some((b"xy".to_vec(), 6)));
+}
+
+#[test]
+fn test_netstring() {
+    let mut codec = NetstringCodec::new();
+
+    assert_eq!(encode(&mut codec, b"hello"), b"5:hello,");
+    assert_eq!(encode(&mut codec, b""), b"0:,");
+    assert_eq!(codec.decode(b"5").unwrap(), None);
+    assert_eq!(codec.decode(b"5:hello").unwrap(), None);
+    assert_eq!(codec.decode(b"5:hello,5:").unwrap(), Some((b"hello".to_vec(), 8)));
+    assert_eq!(codec.decode(b"0:,").unwrap(), Some((vec![], 3)));
+
+    for bad in &[&b"x:"[..], b"05:hello,", b":", b"5:hello!", b"999999999999999999999:"] {
+        assert_eq!(codec.decode(bad).unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_framed() {
+    let mut selector = Selector::new().unwrap();
+    let (mut a, b) = UnixStream::pair().unwrap();
+
+    b.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    let mut framed = Framed::new(b, LengthCodec::u16_be());
+    assert_eq!(framed.read_frame().unwrap(), None);
+    assert!(!framed.is_eof());
+
+    // A frame split across two readiness events is delivered whole.
+    a.write_all(b"\x00\x05hel").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+    assert_eq!(framed.read_frame().unwrap(), None);
+
+    a.write_all(b"lo\x00\x02hi\x00").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+    assert_eq!(framed.read_frame().unwrap(), Some(b"hello".to_vec()));
+    assert_eq!(framed.read_frame().unwrap(), Some(b"hi".to_vec()));
+    assert_eq!(framed.read_frame().unwrap(), None);
+
+    // Writing queues frames until they are flushed.
+    framed.write_frame(b"reply").unwrap();
+    framed.write_frame(b"").unwrap();
+    assert!(framed.wants_writable());
+    assert_eq!(framed.flush_nb().unwrap(), (9, Status::Drained));
+    assert!(!framed.wants_writable());
+
+    let mut buf = [0; 16];
+    assert_eq!(a.read(&mut buf).unwrap(), 9);
+    assert_eq!(&buf[..9], b"\x00\x05reply\x00\x00");
+
+    // The stream ends within the last frame.
+    drop(a);
+    let err = framed.read_frame().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_framed_eof() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut framed = Framed::new(b, LineCodec::new());
+
+    a.write_all(b"one\ntwo\n").unwrap();
+    drop(a);
+
+    assert_eq!(framed.read_frame().unwrap(), Some(b"one".to_vec()));
+    assert!(!framed.is_eof());
+    assert_eq!(framed.read_frame().unwrap(), Some(b"two".to_vec()));
+    assert_eq!(framed.read_frame().unwrap(), None);
+    assert!(framed.is_eof());
+}
+
+#[test]
+fn test_max_frame_size() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut framed = Framed::new(b, LineCodec::new());
+    framed.set_max_frame_size(8);
+
+    let err = framed.write_frame(b"too long a line").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(!framed.wants_writable());
+
+    a.write_all(b"short\n").unwrap();
+    assert_eq!(framed.read_frame().unwrap(), Some(b"short".to_vec()));
+
+    a.write_all(b"no end in sight").unwrap();
+    let err = framed.read_frame().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_framed_partial_writes() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut framed = Framed::new(b, LengthCodec::u16_be());
+
+    // More than the socket buffer holds, so the queue is flushed over several writes.
+    let mut expected = Vec::new();
+    for i in 0..512 {
+        let frame = vec![i as u8; 1000];
+        expected.extend(encode(&mut LengthCodec::u16_be(), &frame));
+        framed.write_frame(&frame).unwrap();
+    }
+
+    let mut received = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        let (_, status) = framed.flush_nb().unwrap();
+        loop {
+            match a.read(&mut buf) {
+                Ok(n) => received.extend_from_slice(&buf[..n]),
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => panic!("{}", err),
+            }
+        }
+        if status == Status::Drained {
+            break;
+        }
+    }
+
+    assert!(!framed.wants_writable());
+    assert_eq!(received, expected);
+}
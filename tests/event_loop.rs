@@ -0,0 +1,122 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::os::unix::net::UnixStream;
+use std::thread;
+use std::time::Duration;
+
+use rivet::{EventLoop, Handler, EventSet, PollOpt, Token};
+
+struct Echo {
+    stream: UnixStream,
+    received: Vec<u8>,
+}
+
+impl Handler for Echo {
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<Echo>, token: Token, evset: EventSet) {
+        assert_eq!(token, Token(0));
+        assert!(evset.is_readable());
+
+        let mut buf = [0; 64];
+        let n = self.stream.read(&mut buf).unwrap();
+        self.received.extend_from_slice(&buf[..n]);
+
+        // Deregistering from within a callback.
+        event_loop.deregister(&self.stream).unwrap();
+        event_loop.shutdown();
+    }
+}
+
+#[test]
+fn test_ready() {
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    stream.set_nonblocking(true).unwrap();
+
+    let mut event_loop = EventLoop::new().unwrap();
+    event_loop.register(&stream, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    peer.write_all(b"ping").unwrap();
+
+    let mut handler = Echo {
+        stream: stream,
+        received: Vec::new(),
+    };
+    event_loop.run(&mut handler).unwrap();
+    assert!(!event_loop.is_running());
+    assert_eq!(handler.received, b"ping");
+}
+
+struct Counter {
+    messages: Vec<u32>,
+    timeouts: Vec<Token>,
+    ticks: usize,
+}
+
+impl Handler for Counter {
+    type Message = u32;
+
+    fn notify(&mut self, event_loop: &mut EventLoop<Counter>, msg: u32) {
+        self.messages.push(msg);
+        if msg == 3 {
+            event_loop.timeout(Token(7), Duration::from_millis(10));
+        }
+    }
+
+    fn timeout(&mut self, event_loop: &mut EventLoop<Counter>, token: Token) {
+        self.timeouts.push(token);
+        event_loop.shutdown();
+    }
+
+    fn tick(&mut self, _: &mut EventLoop<Counter>) {
+        self.ticks += 1;
+    }
+}
+
+#[test]
+fn test_notify_and_timeout() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let sender = event_loop.channel();
+
+    let handle = thread::spawn(move || {
+        for i in 1..4 {
+            thread::sleep(Duration::from_millis(10));
+            sender.send(i).unwrap();
+        }
+    });
+
+    let mut handler = Counter {
+        messages: Vec::new(),
+        timeouts: Vec::new(),
+        ticks: 0,
+    };
+    event_loop.run(&mut handler).unwrap();
+    handle.join().unwrap();
+
+    assert_eq!(handler.messages, vec![1, 2, 3]);
+    assert_eq!(handler.timeouts, vec![Token(7)]);
+    assert!(handler.ticks >= 2);
+}
+
+#[test]
+fn test_run_once() {
+    let mut event_loop = EventLoop::new().unwrap();
+    let mut handler = Counter {
+        messages: Vec::new(),
+        timeouts: Vec::new(),
+        ticks: 0,
+    };
+
+    let timeout = event_loop.timeout(Token(1), Duration::from_millis(10));
+    assert!(event_loop.clear_timeout(timeout));
+
+    event_loop.run_once(&mut handler, Some(Duration::from_millis(50))).unwrap();
+    assert!(handler.timeouts.is_empty());
+    assert_eq!(handler.ticks, 1);
+
+    // Messages sent after the loop is dropped are refused.
+    let sender = event_loop.channel();
+    drop(event_loop);
+    assert!(sender.send(0).is_err());
+}
@@ -0,0 +1,122 @@
+extern crate rivet;
+
+use std::io::Result;
+use std::io::prelude::*;
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventedFd, EventSet, PollOpt, Token};
+
+fn tokens(selector: &mut Selector) -> Vec<Token> {
+    let mut tokens = selector.poll(Some(Duration::from_millis(100)))
+        .unwrap()
+        .map(|fired| fired.token())
+        .collect::<Vec<_>>();
+    tokens.sort();
+    tokens
+}
+
+#[test]
+fn test_tcp() {
+    let mut selector = Selector::new().unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    listener.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(0)]);
+
+    let (server, _) = listener.accept().unwrap();
+    server.register(&mut selector, Token(1), EventSet::readable(), PollOpt::level()).unwrap();
+    client.write_all(b"hello").unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(1)]);
+
+    server.reregister(&mut selector, Token(2), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(2)]);
+
+    server.deregister(&mut selector).unwrap();
+    listener.deregister(&mut selector).unwrap();
+    assert!(tokens(&mut selector).is_empty());
+}
+
+#[test]
+fn test_udp() {
+    let mut selector = Selector::new().unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.send_to(b"datagram", socket.local_addr().unwrap()).unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(0)]);
+}
+
+// A source made of two streams, registered under consecutive tokens.
+struct Duplex {
+    rx: UnixStream,
+    tx: UnixStream,
+}
+
+impl Evented for Duplex {
+    fn register(&self,
+                selector: &mut Selector,
+                token: Token,
+                evset: EventSet,
+                opts: PollOpt)
+                -> Result<()> {
+        try!(self.rx.register(selector, token, evset & EventSet::readable(), opts));
+        self.tx.register(selector, Token(token.0 + 1), evset & EventSet::writable(), opts)
+    }
+
+    fn reregister(&self,
+                  selector: &mut Selector,
+                  token: Token,
+                  evset: EventSet,
+                  opts: PollOpt)
+                  -> Result<()> {
+        try!(self.rx.reregister(selector, token, evset & EventSet::readable(), opts));
+        self.tx.reregister(selector, Token(token.0 + 1), evset & EventSet::writable(), opts)
+    }
+
+    fn deregister(&self, selector: &mut Selector) -> Result<()> {
+        try!(self.rx.deregister(selector));
+        self.tx.deregister(selector)
+    }
+}
+
+#[test]
+fn test_composite() {
+    let mut selector = Selector::new().unwrap();
+
+    let (rx, mut peer) = UnixStream::pair().unwrap();
+    let (tx, _other) = UnixStream::pair().unwrap();
+    let duplex = Duplex { rx: rx, tx: tx };
+
+    duplex.register(&mut selector, Token(0), EventSet::all(), PollOpt::level()).unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(1)]);
+
+    peer.write_all(b"ready").unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(0), Token(1)]);
+
+    duplex.deregister(&mut selector).unwrap();
+    assert!(tokens(&mut selector).is_empty());
+}
+
+#[test]
+fn test_evented_fd() {
+    let mut selector = Selector::new().unwrap();
+
+    let (stream, mut peer) = UnixStream::pair().unwrap();
+    let fd = stream.as_raw_fd();
+
+    EventedFd(&fd).register(&mut selector, Token(3), EventSet::readable(), PollOpt::level())
+        .unwrap();
+    peer.write_all(b"fd").unwrap();
+    assert_eq!(tokens(&mut selector), vec![Token(3)]);
+
+    EventedFd(&fd).deregister(&mut selector).unwrap();
+    assert!(tokens(&mut selector).is_empty());
+}
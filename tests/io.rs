@@ -0,0 +1,219 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::{IoSlice, IoSliceMut, Result, ErrorKind};
+
+use rivet::Pipe;
+use rivet::net::UnixStream;
+use rivet::io::{ReadExt, WriteExt, Status, NbBufReader, NbBufWriter};
+
+#[test]
+fn test_read_nb() {
+    let (mut tx, mut rx) = Pipe::new().unwrap().split();
+    let mut buf = [0; 8];
+
+    assert_eq!(rx.read_nb(&mut buf).unwrap(), (0, Status::Drained));
+
+    tx.write_all(b"abc").unwrap();
+    assert_eq!(rx.read_nb(&mut buf).unwrap(), (3, Status::Drained));
+
+    tx.write_all(b"0123456789").unwrap();
+    assert_eq!(rx.read_nb(&mut buf).unwrap(), (8, Status::BufferFull));
+    assert_eq!(&buf, b"01234567");
+
+    // Bytes still queued are returned before end-of-file is reported.
+    drop(tx);
+    assert_eq!(rx.read_nb(&mut buf).unwrap(), (2, Status::Eof));
+    assert_eq!(rx.read_nb(&mut buf).unwrap(), (0, Status::Eof));
+}
+
+#[test]
+fn test_write_nb() {
+    let (mut tx, mut rx) = Pipe::new().unwrap().split();
+
+    assert_eq!(tx.write_nb(b"hello").unwrap(), (5, Status::Drained));
+
+    let buf = vec![0; 1 << 20];
+    let (n, status) = tx.write_nb(&buf).unwrap();
+    assert!(n < buf.len());
+    assert_eq!(status, Status::BufferFull);
+
+    let mut sink = vec![0; 1 << 20];
+    assert_eq!(rx.read_nb(&mut sink).unwrap(), (n + 5, Status::Drained));
+}
+
+// A writer that accepts a fixed number of bytes and then no more.
+struct Limited(usize);
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = ::std::cmp::min(self.0, buf.len());
+        self.0 -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_nb_eof() {
+    let mut writer = Limited(4);
+    assert_eq!(writer.write_nb(b"abcdef").unwrap(), (4, Status::Eof));
+}
+
+#[test]
+fn test_vectored() {
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+
+    let bufs = [IoSlice::new(b"head"), IoSlice::new(b""), IoSlice::new(b"body")];
+    assert_eq!(a.write_nb_vectored(&bufs).unwrap(), (8, Status::Drained));
+
+    let mut head = [0; 3];
+    let mut body = [0; 8];
+    {
+        let mut bufs = [IoSliceMut::new(&mut head), IoSliceMut::new(&mut body)];
+        assert_eq!(b.read_nb_vectored(&mut bufs).unwrap(), (8, Status::Drained));
+    }
+    assert_eq!(&head, b"hea");
+    assert_eq!(&body[..5], b"dbody");
+
+    a.write_all(b"0123456789").unwrap();
+    drop(a);
+    {
+        let mut bufs = [IoSliceMut::new(&mut head), IoSliceMut::new(&mut body)];
+        assert_eq!(b.read_nb_vectored(&mut bufs).unwrap(), (10, Status::Eof));
+    }
+    assert_eq!(&head, b"012");
+    assert_eq!(&body[..7], b"3456789");
+}
+
+#[test]
+fn test_vectored_full() {
+    let (mut tx, mut rx) = Pipe::new().unwrap().split();
+
+    let head = vec![1; 1000];
+    let body = vec![2; 1 << 20];
+    let (n, status) = tx.write_nb_vectored(&[IoSlice::new(&head), IoSlice::new(&body)]).unwrap();
+    assert!(n > head.len() && n < head.len() + body.len());
+    assert_eq!(status, Status::BufferFull);
+
+    let mut sink = vec![0; 1 << 21];
+    assert_eq!(rx.read_nb(&mut sink).unwrap(), (n, Status::Drained));
+    assert!(sink[..1000].iter().all(|&b| b == 1));
+    assert!(sink[1000..n].iter().all(|&b| b == 2));
+}
+
+// A writer that accepts at most three bytes per call, recording everything written.
+struct Trickle(Vec<u8>);
+
+impl Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = ::std::cmp::min(3, buf.len());
+        self.0.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_vectored_partial() {
+    let mut writer = Trickle(Vec::new());
+
+    let bufs = [IoSlice::new(b"ab"), IoSlice::new(b"cdefg"), IoSlice::new(b""), IoSlice::new(b"h")];
+    assert_eq!(writer.write_nb_vectored(&bufs).unwrap(), (8, Status::Drained));
+    assert_eq!(writer.0, b"abcdefgh");
+}
+
+#[test]
+fn test_buf_reader() {
+    let (mut tx, rx) = Pipe::new().unwrap().split();
+    let mut reader = NbBufReader::with_capacity(8, rx);
+
+    assert_eq!(reader.fill().unwrap(), (0, Status::Drained));
+    assert_eq!(reader.read(&mut [0; 4]).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    tx.write_all(b"abcdef").unwrap();
+    assert_eq!(reader.fill().unwrap(), (6, Status::Drained));
+    reader.consume(4);
+
+    // The buffered bytes wrap around the end of the ring, and are joined by `buffer`.
+    tx.write_all(b"ghijkl").unwrap();
+    assert_eq!(reader.fill().unwrap(), (6, Status::BufferFull));
+    assert_eq!(reader.buffer(), b"efghijkl");
+
+    // A full buffer grows on the next fill.
+    tx.write_all(b"mn").unwrap();
+    assert_eq!(reader.fill().unwrap(), (2, Status::Drained));
+    assert_eq!(reader.len(), 10);
+
+    let mut buf = [0; 16];
+    assert_eq!(reader.read(&mut buf).unwrap(), 10);
+    assert_eq!(&buf[..10], b"efghijklmn");
+    assert!(reader.is_empty());
+
+    drop(tx);
+    assert_eq!(reader.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_buf_writer() {
+    let (tx, mut rx) = Pipe::new().unwrap().split();
+    let mut writer = NbBufWriter::with_watermarks(1 << 16, 1 << 20, tx);
+    assert!(!writer.wants_writable());
+
+    writer.write_all(b"queued").unwrap();
+    assert_eq!(writer.len(), 6);
+    assert_eq!(writer.flush_nb().unwrap(), (6, Status::Drained));
+    assert!(!writer.wants_writable());
+
+    // Writes stop at the high watermark.
+    let buf = vec![0; 2 << 20];
+    assert_eq!(writer.write_nb(&buf).unwrap(), (1 << 20, Status::BufferFull));
+    assert!(writer.above_high_watermark());
+    assert_eq!(writer.write(b"x").unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    // The pipe fills before the queue is written out.
+    let (n, status) = writer.flush_nb().unwrap();
+    assert!(n > 0);
+    assert_eq!(status, Status::BufferFull);
+    assert!(writer.wants_writable());
+    assert_eq!(writer.flush().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let mut sink = vec![0; 1 << 16];
+    while !writer.below_low_watermark() {
+        rx.read_nb(&mut sink).unwrap();
+        writer.flush_nb().unwrap();
+    }
+    assert!(!writer.above_high_watermark());
+
+    while writer.wants_writable() {
+        rx.read_nb(&mut sink).unwrap();
+        writer.flush_nb().unwrap();
+    }
+    writer.flush().unwrap();
+}
+
+#[test]
+fn test_buf_writer_queue() {
+    let (tx, rx) = Pipe::new().unwrap().split();
+    let mut writer = NbBufWriter::with_watermarks(0, 4, tx);
+
+    // Queued messages are kept whole, even past the high watermark.
+    writer.queue(b"message");
+    assert!(writer.above_high_watermark());
+    assert_eq!(writer.write(b"more").unwrap_err().kind(), ErrorKind::WouldBlock);
+    assert_eq!(writer.flush_nb().unwrap(), (7, Status::Drained));
+    assert!(writer.below_low_watermark());
+
+    assert_eq!(writer.write(b"abcdef").unwrap(), 4);
+    writer.flush().unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!((&rx).read(&mut buf).unwrap(), 11);
+    assert_eq!(&buf[..11], b"messageabcd");
+}
@@ -0,0 +1,79 @@
+extern crate libc;
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token, Pipe};
+
+#[test]
+fn test_pipe() {
+    let mut selector = Selector::new().unwrap();
+    let (mut tx, mut rx) = Pipe::new().unwrap().split();
+
+    for fd in &[tx.as_raw_fd(), rx.as_raw_fd()] {
+        unsafe {
+            assert!(libc::fcntl(*fd, libc::F_GETFL) & libc::O_NONBLOCK != 0);
+            assert!(libc::fcntl(*fd, libc::F_GETFD) & libc::FD_CLOEXEC != 0);
+        }
+    }
+
+    rx.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    tx.register(&mut selector, Token(1), EventSet::writable(), PollOpt::level()).unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(rx.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let fired = selector.poll(Some(Duration::from_secs(1))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(1));
+
+    tx.write_all(b"piped").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 2);
+    assert_eq!(rx.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"piped");
+
+    // The receiver sees end-of-file once the sender is gone.
+    tx.deregister(&mut selector).unwrap();
+    drop(tx);
+    assert_eq!(rx.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_full() {
+    let pipe = Pipe::new().unwrap();
+    let buf = [0; 4096];
+
+    let mut written = 0;
+    loop {
+        match pipe.sender().write(&buf) {
+            Ok(n) => written += n,
+            Err(e) => {
+                assert_eq!(e.kind(), ErrorKind::WouldBlock);
+                break;
+            }
+        }
+    }
+
+    let mut read = 0;
+    let mut buf = [0; 4096];
+    while let Ok(n) = pipe.receiver().read(&mut buf) {
+        read += n;
+    }
+    assert_eq!(read, written);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_capacity() {
+    let pipe = Pipe::new().unwrap();
+
+    let size = pipe.set_capacity(8192).unwrap();
+    assert!(size >= 8192);
+    assert_eq!(pipe.capacity().unwrap(), size);
+
+    let (tx, rx) = pipe.split();
+    assert_eq!(rx.set_capacity(size * 2).unwrap(), tx.capacity().unwrap());
+}
@@ -0,0 +1,150 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::net::{self, SocketAddr};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rivet::{EventLoop, Handler, EventSet, PollOpt, Token};
+use rivet::{ThreadPoolLoop, PoolHandler, Distribution, Worker};
+use rivet::net::TcpStream;
+
+// Echoes whatever its connections send, releasing each connection once the peer closes it.
+struct Echo {
+    worker: Worker,
+    conns: Vec<Option<TcpStream>>,
+}
+
+impl Handler for Echo {
+    type Message = ();
+
+    fn ready(&mut self, event_loop: &mut EventLoop<Echo>, token: Token, _: EventSet) {
+        let mut closed = false;
+        if let Some(ref mut stream) = self.conns[token.0] {
+            let mut buf = [0; 1024];
+            loop {
+                match stream.read(&mut buf) {
+                    Ok(0) => closed = true,
+                    Ok(n) => {
+                        stream.write_all(&buf[..n]).unwrap();
+                        continue;
+                    }
+                    Err(ref err) if err.kind() == ErrorKind::WouldBlock => {}
+                    Err(_) => closed = true,
+                }
+                break;
+            }
+        }
+
+        if closed {
+            let stream = self.conns[token.0].take().unwrap();
+            event_loop.deregister(&stream).unwrap();
+            self.worker.release();
+        }
+    }
+}
+
+impl PoolHandler for Echo {
+    fn accept(&mut self, event_loop: &mut EventLoop<Echo>, stream: TcpStream, _: SocketAddr) {
+        let token = Token(self.conns.len());
+        event_loop.register(&stream, token, EventSet::readable(), PollOpt::level()).unwrap();
+        self.conns.push(Some(stream));
+    }
+}
+
+fn echo_pool(threads: usize, distribution: Distribution) -> ThreadPoolLoop<Echo> {
+    let addr = "127.0.0.1:0".parse().unwrap();
+    ThreadPoolLoop::bind(&addr, threads, distribution, |worker| {
+            Echo {
+                worker: worker,
+                conns: Vec::new(),
+            }
+        })
+        .unwrap()
+}
+
+// Connects to the pool and waits for a message to be echoed, so the connection has been
+// assigned to a thread.
+fn connect(pool: &ThreadPoolLoop<Echo>) -> net::TcpStream {
+    let mut client = net::TcpStream::connect(pool.local_addr()).unwrap();
+    client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+    let mut buf = [0; 5];
+    client.write_all(b"hello").unwrap();
+    client.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+    client
+}
+
+fn loads(pool: &ThreadPoolLoop<Echo>) -> Vec<usize> {
+    (0..pool.threads()).map(|i| pool.load(i)).collect()
+}
+
+fn wait_for_loads(pool: &ThreadPoolLoop<Echo>, expected: &[usize]) {
+    let start = Instant::now();
+    while loads(pool) != expected {
+        assert!(start.elapsed() < Duration::from_secs(5), "loads are {:?}", loads(pool));
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn test_round_robin() {
+    let pool = echo_pool(3, Distribution::RoundRobin);
+    assert_eq!(pool.threads(), 3);
+
+    let clients = (0..6).map(|_| connect(&pool)).collect::<Vec<_>>();
+    assert_eq!(loads(&pool), [2, 2, 2]);
+
+    drop(clients);
+    wait_for_loads(&pool, &[0, 0, 0]);
+    pool.shutdown();
+    pool.join().unwrap();
+}
+
+#[test]
+fn test_least_loaded() {
+    let pool = echo_pool(2, Distribution::LeastLoaded);
+
+    let first = connect(&pool);
+    let second = connect(&pool);
+    assert_eq!(loads(&pool), [1, 1]);
+
+    // Once a thread's connection closes it is the least loaded, and ties go to the first thread.
+    drop(first);
+    wait_for_loads(&pool, &[0, 1]);
+    let _third = connect(&pool);
+    assert_eq!(loads(&pool), [1, 1]);
+    let _fourth = connect(&pool);
+    assert_eq!(loads(&pool), [2, 1]);
+
+    drop(second);
+    pool.shutdown();
+    pool.join().unwrap();
+}
+
+#[test]
+fn test_reuseport() {
+    let pool = echo_pool(2, Distribution::ReusePort);
+
+    let clients = (0..8).map(|_| connect(&pool)).collect::<Vec<_>>();
+    assert_eq!(loads(&pool).iter().sum::<usize>(), 8);
+
+    drop(clients);
+    wait_for_loads(&pool, &[0, 0]);
+    pool.shutdown();
+    pool.join().unwrap();
+}
+
+#[test]
+fn test_no_threads() {
+    let addr = "127.0.0.1:0".parse().unwrap();
+    let res = ThreadPoolLoop::bind(&addr, 0, Distribution::RoundRobin, |worker| {
+        Echo {
+            worker: worker,
+            conns: Vec::new(),
+        }
+    });
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,191 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread;
+use std::time::Duration;
+
+use rivet::{Reactor, Async};
+use rivet::net::UnixStream;
+
+struct Counter(AtomicUsize);
+
+impl Wake for Counter {
+    fn wake(self: Arc<Self>) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn counter() -> (Arc<Counter>, Waker) {
+    let counter = Arc::new(Counter(AtomicUsize::new(0)));
+    let waker = Waker::from(counter.clone());
+    (counter, waker)
+}
+
+fn woken(counter: &Counter) -> usize {
+    counter.0.load(Ordering::SeqCst)
+}
+
+#[test]
+fn test_poll_read() {
+    let reactor = Reactor::new().unwrap();
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut b = Async::new(b, &reactor).unwrap();
+
+    let (counter, waker) = counter();
+    let mut cx = Context::from_waker(&waker);
+    let mut buf = [0; 8];
+
+    assert!(b.poll_read(&mut cx, &mut buf).is_pending());
+    assert_eq!(reactor.turn(Some(Duration::from_millis(10))).unwrap(), 0);
+    assert_eq!(woken(&counter), 0);
+
+    a.write_all(b"hello").unwrap();
+    assert_eq!(reactor.turn(Some(Duration::from_secs(1))).unwrap(), 1);
+    assert_eq!(woken(&counter), 1);
+
+    match b.poll_read(&mut cx, &mut buf) {
+        Poll::Ready(Ok(5)) => assert_eq!(&buf[..5], b"hello"),
+        res => panic!("unexpected poll result: {:?}", res),
+    }
+    assert!(b.poll_read(&mut cx, &mut buf).is_pending());
+}
+
+#[test]
+fn test_poll_write() {
+    let reactor = Reactor::new().unwrap();
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut a = Async::new(a, &reactor).unwrap();
+
+    let (counter, waker) = counter();
+    let mut cx = Context::from_waker(&waker);
+    let chunk = [0; 4096];
+
+    // Fill the socket until writing would block.
+    let mut written = 0;
+    while let Poll::Ready(n) = a.poll_write(&mut cx, &chunk) {
+        written += n.unwrap();
+    }
+    assert_eq!(woken(&counter), 0);
+
+    let mut buf = vec![0; written];
+    b.read_exact(&mut buf).unwrap();
+    assert_eq!(reactor.turn(Some(Duration::from_secs(1))).unwrap(), 1);
+    assert_eq!(woken(&counter), 1);
+
+    match a.poll_write(&mut cx, &chunk) {
+        Poll::Ready(Ok(n)) => assert!(n > 0),
+        res => panic!("unexpected poll result: {:?}", res),
+    }
+}
+
+#[test]
+fn test_read_and_write() {
+    let reactor = Reactor::new().unwrap();
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut a = Async::new(a, &reactor).unwrap();
+
+    let (writes, write_waker) = counter();
+    let (reads, read_waker) = counter();
+    let mut write_cx = Context::from_waker(&write_waker);
+    let mut read_cx = Context::from_waker(&read_waker);
+    let chunk = [0; 4096];
+    let mut buf = [0; 8];
+
+    // The writer waits for the full socket to drain while the reader waits for input.
+    let mut written = 0;
+    while let Poll::Ready(n) = a.poll_write(&mut write_cx, &chunk) {
+        written += n.unwrap();
+    }
+    assert!(a.poll_read(&mut read_cx, &mut buf).is_pending());
+
+    b.write_all(b"x").unwrap();
+    reactor.turn(Some(Duration::from_secs(1))).unwrap();
+    assert_eq!(woken(&reads), 1);
+    match a.poll_read(&mut read_cx, &mut buf) {
+        Poll::Ready(Ok(1)) => {}
+        res => panic!("unexpected poll result: {:?}", res),
+    }
+
+    // The read disarmed the shared registration, so the writer was woken to poll again and re-arm
+    // it, and still hears of the socket draining once the reader has stopped polling.
+    assert_eq!(woken(&writes), 1);
+    assert!(a.poll_write(&mut write_cx, &chunk).is_pending());
+
+    let mut drained = vec![0; written];
+    b.read_exact(&mut drained).unwrap();
+    assert_eq!(reactor.turn(Some(Duration::from_secs(1))).unwrap(), 1);
+    assert_eq!(woken(&writes), 2);
+}
+
+#[test]
+fn test_hup() {
+    let reactor = Reactor::new().unwrap();
+    let (a, b) = UnixStream::pair().unwrap();
+    let mut b = Async::new(b, &reactor).unwrap();
+
+    let (counter, waker) = counter();
+    let mut cx = Context::from_waker(&waker);
+    let mut buf = [0; 8];
+
+    assert!(b.poll_read(&mut cx, &mut buf).is_pending());
+    drop(a);
+    assert_eq!(reactor.turn(Some(Duration::from_secs(1))).unwrap(), 1);
+    assert_eq!(woken(&counter), 1);
+
+    match b.poll_read(&mut cx, &mut buf) {
+        Poll::Ready(Ok(0)) => {}
+        res => panic!("unexpected poll result: {:?}", res),
+    }
+}
+
+#[test]
+fn test_register_while_turning() {
+    let reactor = Reactor::new().unwrap();
+    let (counter, waker) = counter();
+
+    let turning = reactor.clone();
+    let turned = counter.clone();
+    let handle = thread::spawn(move || {
+        while woken(&turned) == 0 {
+            turning.turn(None).unwrap();
+        }
+    });
+
+    // Registering and arming the source must not wait for the blocked turn to return.
+    thread::sleep(Duration::from_millis(50));
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut b = Async::new(b, &reactor).unwrap();
+
+    let mut cx = Context::from_waker(&waker);
+    let mut buf = [0; 8];
+    assert!(b.poll_read(&mut cx, &mut buf).is_pending());
+
+    a.write_all(b"x").unwrap();
+    handle.join().unwrap();
+    assert_eq!(woken(&counter), 1);
+}
+
+#[test]
+fn test_into_inner() {
+    let reactor = Reactor::new().unwrap();
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let mut b = Async::new(b, &reactor).unwrap();
+
+    let (counter, waker) = counter();
+    let mut cx = Context::from_waker(&waker);
+    let mut buf = [0; 8];
+    assert!(b.poll_read(&mut cx, &mut buf).is_pending());
+
+    // Once deregistered, the source no longer wakes its task.
+    let mut b = b.into_inner();
+    a.write_all(b"x").unwrap();
+    assert_eq!(reactor.turn(Some(Duration::from_millis(50))).unwrap(), 0);
+    assert_eq!(woken(&counter), 0);
+
+    assert_eq!(b.read(&mut buf).unwrap(), 1);
+    assert_eq!(b.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+}
@@ -0,0 +1,160 @@
+extern crate rivet;
+
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::future::{self, Future};
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rivet::Async;
+use rivet::net::{UnixListener, UnixStream};
+use rivet::rt::{self, LocalExecutor};
+
+fn socket_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rivet-{}-{}.sock", process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Polls a future in place, for building futures out of others by hand.
+fn poll<F: Future + Unpin>(mut future: F, cx: &mut Context) -> Poll<F::Output> {
+    Pin::new(&mut future).poll(cx)
+}
+
+#[test]
+fn test_block_on() {
+    assert_eq!(rt::block_on(future::ready(7)).unwrap(), 7);
+}
+
+#[test]
+fn test_sleep() {
+    let start = Instant::now();
+    rt::block_on(rt::sleep(Duration::from_millis(50))).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // A deadline already passed completes without waiting.
+    rt::block_on(rt::sleep_until(start)).unwrap();
+}
+
+#[test]
+fn test_sleep_forever() {
+    let mut forever = rt::sleep(Duration::MAX);
+    let mut short = rt::sleep(Duration::from_millis(20));
+
+    rt::block_on(future::poll_fn(|cx| {
+            assert!(Pin::new(&mut forever).poll(cx).is_pending());
+            Pin::new(&mut short).poll(cx)
+        }))
+        .unwrap();
+    assert!(forever.deadline() > Instant::now() + Duration::from_secs(86400 * 365));
+}
+
+#[test]
+fn test_spawn_local() {
+    let executor = LocalExecutor::new().unwrap();
+    let order = Rc::new(RefCell::new(Vec::new()));
+
+    let mut handles = Vec::new();
+    for &(id, ms) in &[(1, 30), (2, 10), (3, 20)] {
+        let order = order.clone();
+        let mut sleep = rt::sleep(Duration::from_millis(ms));
+        handles.push(executor.spawn_local(future::poll_fn(move |cx| {
+            match Pin::new(&mut sleep).poll(cx) {
+                Poll::Ready(()) => {
+                    order.borrow_mut().push(id);
+                    Poll::Ready(id * 10)
+                }
+                Poll::Pending => Poll::Pending,
+            }
+        })));
+    }
+
+    let last = handles.remove(0);
+    assert_eq!(executor.block_on(last).unwrap(), 10);
+    assert_eq!(*order.borrow(), [2, 3, 1]);
+    assert_eq!(executor.block_on(handles.remove(1)).unwrap(), 30);
+}
+
+#[test]
+fn test_wake_from_thread() {
+    let state: Arc<Mutex<(bool, Option<Waker>)>> = Arc::new(Mutex::new((false, None)));
+
+    let remote = state.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        let mut state = remote.lock().unwrap();
+        state.0 = true;
+        if let Some(waker) = state.1.take() {
+            waker.wake();
+        }
+    });
+
+    rt::block_on(future::poll_fn(|cx| {
+        let mut state = state.lock().unwrap();
+        if state.0 {
+            Poll::Ready(())
+        } else {
+            state.1 = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }))
+        .unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_echo() {
+    let executor = LocalExecutor::new().unwrap();
+    let path = socket_path("rt-echo");
+
+    let listener = UnixListener::bind(&path).unwrap();
+    let mut listener = Async::new(listener, executor.reactor()).unwrap();
+
+    let mut conn = None;
+    let mut buf = [0; 16];
+    let server = executor.spawn_local(future::poll_fn(move |cx| {
+        loop {
+            let stream = match conn {
+                Some(ref mut stream) => stream,
+                None => {
+                    match poll(rt::accept(&mut listener), cx) {
+                        Poll::Ready(res) => conn = Some(res.unwrap().0),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                    continue;
+                }
+            };
+
+            let n = match poll(rt::read(stream, &mut buf), cx) {
+                Poll::Ready(res) => res.unwrap(),
+                Poll::Pending => return Poll::Pending,
+            };
+            if n == 0 {
+                return Poll::Ready(());
+            }
+            assert_eq!(poll(rt::write(stream, &buf[..n]), cx).map(Result::unwrap), Poll::Ready(n));
+        }
+    }));
+
+    let client = UnixStream::connect(&path).unwrap();
+    let mut client = Async::new(client, executor.reactor()).unwrap();
+    client.get_mut().write_all(b"ping").unwrap();
+
+    let mut reply = [0; 4];
+    let n = executor.block_on(future::poll_fn(|cx| poll(rt::read(&mut client, &mut reply), cx)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(&reply[..n], b"ping");
+
+    drop(client);
+    executor.block_on(server).unwrap();
+    fs::remove_file(&path).unwrap();
+}
@@ -1,131 +1,256 @@
 extern crate rivet;
-extern crate time;
 extern crate libc;
 
-use std::mem;
-use std::io;
 use std::io::prelude::*;
-use std::os::unix::io::RawFd;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::Instant;
+use std::u64;
+use rivet::{Selector, BackendKind, EventSet, PollOpt, Token, Waker, Pipe};
+use std::time::Duration;
 
-use rivet::{Selector, EventSet};
-use time::Duration;
-
-struct Pipe {
-    read: libc::c_int,
-    write: libc::c_int,
+// Runs `test` against a `Selector` for every backend compiled into the crate, skipping `io_uring`
+// if the running kernel does not support or permit it.
+fn each_backend<F>(test: F)
+    where F: Fn(Selector)
+{
+    for kind in BackendKind::available() {
+        let selector = match Selector::with_backend(kind) {
+            Err(_) if kind == BackendKind::Uring => continue,
+            res => res.unwrap(),
+        };
+        test(selector);
+    }
 }
 
-impl Pipe {
-    fn new() -> io::Result<Pipe> {
-        let mut fds = [0 as libc::c_int; 2];
-        unsafe {
-            let ret = libc::pipe(mem::transmute(&mut fds));
-            if ret != 0 {
-                return Err(io::Error::last_os_error());
-            }
+#[test]
+fn test_poll_timeout() {
+    each_backend(|mut selector| {
+        fn count_events(selector: &mut Selector) -> usize {
+            selector.poll(Some(Duration::from_millis(100))).unwrap().count()
         }
-        Ok(Pipe {
-            read: fds[0],
-            write: fds[1],
-        })
-    }
+
+        let (mut tx, rx) = Pipe::new().unwrap().split();
+
+        selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+            .unwrap();
+
+        assert_eq!(count_events(&mut selector), 0);
+        tx.write_all(b"hello world").unwrap();
+        assert_eq!(count_events(&mut selector), 1);
+    });
 }
 
-impl Read for Pipe {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let ret = unsafe {
-            let ptr = mem::transmute(buf.as_ptr());
-            libc::read(self.read, ptr, buf.len() as libc::size_t)
-        };
-        match ret {
-            -1 => Err(io::Error::last_os_error()),
-            n => Ok(n as usize),
+#[test]
+fn test_poll() {
+    each_backend(|mut selector| {
+        fn count_events(selector: &mut Selector) -> usize {
+            selector.poll(None).unwrap().count()
         }
-    }
+
+        let (mut tx1, mut rx1) = Pipe::new().unwrap().split();
+        let (mut tx2, rx2) = Pipe::new().unwrap().split();
+
+        selector.register(rx1.as_raw_fd(), Token(1), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        selector.register(rx2.as_raw_fd(), Token(2), EventSet::readable(), PollOpt::level())
+            .unwrap();
+
+        tx1.write_all(b"twelve bytes").unwrap();
+        assert_eq!(count_events(&mut selector), 1);
+        tx2.write_all(b"more data").unwrap();
+        assert_eq!(count_events(&mut selector), 2);
+        let mut buf = [0; 12];
+        assert_eq!(rx1.read(&mut buf).unwrap(), 12);
+        assert_eq!(count_events(&mut selector), 1);
+    });
 }
 
-impl Write for Pipe {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let ret = unsafe {
-            let ptr = mem::transmute(buf.as_ptr());
-            libc::write(self.write, ptr, buf.len() as libc::size_t)
-        };
-        match ret {
-            -1 => Err(io::Error::last_os_error()),
-            n => Ok(n as usize),
+#[test]
+fn test_deregister() {
+    each_backend(|mut selector| {
+        fn first_token(selector: &mut Selector) -> Token {
+            selector.poll(None).unwrap().next().unwrap().token()
         }
-    }
 
-    fn flush(&mut self) -> io::Result<()> {
-        Ok(())
-    }
+        let (mut tx1, rx1) = Pipe::new().unwrap().split();
+        let (mut tx2, rx2) = Pipe::new().unwrap().split();
+
+        selector.register(rx1.as_raw_fd(), Token(1), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        selector.register(rx2.as_raw_fd(), Token(2), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        tx1.write_all(b"abc").unwrap();
+        tx2.write_all(b"def").unwrap();
+
+        selector.deregister(rx1.as_raw_fd()).unwrap();
+        assert_eq!(first_token(&mut selector), Token(2));
+        selector.register(rx1.as_raw_fd(), Token(1), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        selector.deregister(rx2.as_raw_fd()).unwrap();
+        assert_eq!(first_token(&mut selector), Token(1));
+    });
+}
+
+#[test]
+fn test_registration_errors() {
+    each_backend(|mut selector| {
+        let (_tx, rx) = Pipe::new().unwrap().split();
+        let fd = rx.as_raw_fd();
+
+        let err = selector.reregister(fd, Token(0), EventSet::readable(), PollOpt::level())
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+        assert_eq!(selector.deregister(fd).unwrap_err().raw_os_error(), Some(libc::ENOENT));
+
+        selector.register(fd, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+        let err = selector.register(fd, Token(1), EventSet::readable(), PollOpt::level())
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+
+        selector.deregister(fd).unwrap();
+        assert_eq!(selector.deregister(fd).unwrap_err().raw_os_error(), Some(libc::ENOENT));
+    });
 }
 
-impl Drop for Pipe {
-    fn drop(&mut self) {
+#[test]
+fn test_token_dup() {
+    each_backend(|mut selector| {
+        let (mut tx, rx) = Pipe::new().unwrap().split();
+
+        let dup = unsafe { libc::dup(rx.as_raw_fd()) };
+        assert!(dup != -1);
+
+        selector.register(rx.as_raw_fd(), Token(10), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        selector.register(dup, Token(11), EventSet::readable(), PollOpt::level()).unwrap();
+        tx.write_all(b"dup").unwrap();
+
+        let mut tokens = selector.poll(None).unwrap().map(|f| f.token()).collect::<Vec<_>>();
+        tokens.sort();
+        assert_eq!(tokens, vec![Token(10), Token(11)]);
+
+        selector.deregister(dup).unwrap();
         unsafe {
-            libc::close(self.read);
-            libc::close(self.write);
+            libc::close(dup);
         }
-    }
+    });
 }
 
 #[test]
-fn test_poll_timeout() {
-    fn count_events(selector: &mut Selector) -> usize {
-        selector.poll_timeout(Duration::milliseconds(100)).unwrap().count()
-    }
+fn test_edge() {
+    each_backend(|mut selector| {
+        let (mut tx, mut rx) = Pipe::new().unwrap().split();
 
-    let mut pipe = Pipe::new().unwrap();
-    let mut selector = Selector::new().unwrap();
+        selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::edge()).unwrap();
+        tx.write_all(b"edge").unwrap();
 
-    selector.register(pipe.read, EventSet::readable()).unwrap();
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 1);
+        // Still readable, but no new edge.
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
 
-    assert_eq!(count_events(&mut selector), 0);
-    pipe.write_all(b"hello world").unwrap();
-    assert_eq!(count_events(&mut selector), 1);
+        let mut buf = [0; 4];
+        assert_eq!(rx.read(&mut buf).unwrap(), 4);
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+        tx.write_all(b"more").unwrap();
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 1);
+    });
 }
 
 #[test]
-fn test_poll() {
-    fn count_events(selector: &mut Selector) -> usize {
-        selector.poll().unwrap().count()
-    }
+fn test_oneshot() {
+    each_backend(|mut selector| {
+        let (mut tx, rx) = Pipe::new().unwrap().split();
 
-    let mut pipe1 = Pipe::new().unwrap();
-    let mut pipe2 = Pipe::new().unwrap();
-    let mut selector = Selector::new().unwrap();
+        selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::oneshot())
+            .unwrap();
+        tx.write_all(b"once").unwrap();
 
-    selector.register(pipe1.read, EventSet::readable()).unwrap();
-    selector.register(pipe2.read, EventSet::readable()).unwrap();
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 1);
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
 
-    pipe1.write_all(b"twelve bytes").unwrap();
-    assert_eq!(count_events(&mut selector), 1);
-    pipe2.write_all(b"more data").unwrap();
-    assert_eq!(count_events(&mut selector), 2);
-    let mut buf = [0; 12];
-    assert_eq!(pipe1.read(&mut buf).unwrap(), 12);
-    assert_eq!(count_events(&mut selector), 1);
+        selector.reregister(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::oneshot())
+            .unwrap();
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 1);
+    });
 }
 
 #[test]
-fn test_deregister() {
-    fn first_fd(selector: &mut Selector) -> RawFd {
-        selector.poll().unwrap().next().unwrap().fd()
-    }
+fn test_waker() {
+    each_backend(|mut selector| {
+        let waker = Waker::new(&mut selector).unwrap();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            waker.wake().unwrap();
+            waker.wake().unwrap();
+        });
+
+        let fired = selector.poll(None).unwrap().collect::<Vec<_>>();
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].is_wakeup());
+
+        handle.join().unwrap();
+        // Both wakeups were coalesced and the waker has been reset.
+        assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+    });
+}
+
+#[test]
+fn test_timeout_with_events() {
+    each_backend(|mut selector| {
+        let (mut tx, rx) = Pipe::new().unwrap().split();
+
+        selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        selector.set_timeout(Token(1), Duration::from_millis(20));
+
+        thread::sleep(Duration::from_millis(30));
+        tx.write_all(b"hello").unwrap();
+
+        // Readiness and the expired timeout are reported by the same poll.
+        let mut fired = selector.poll(None).unwrap().collect::<Vec<_>>();
+        fired.sort();
+        assert_eq!(fired.len(), 2);
+        assert_eq!(fired[0].token(), Token(0));
+        assert!(!fired[0].is_timeout());
+        assert_eq!(fired[1].token(), Token(1));
+        assert!(fired[1].is_timeout());
+    });
+}
+
+#[test]
+fn test_poll_timeout_bounds() {
+    each_backend(|mut selector| {
+        let waker = Waker::new(&mut selector).unwrap();
+
+        // Sub-millisecond timeouts are rounded up rather than truncated to a busy-poll.
+        let start = Instant::now();
+        assert_eq!(selector.poll(Some(Duration::from_micros(500))).unwrap().count(), 0);
+        assert!(start.elapsed() >= Duration::from_micros(500));
+
+        // Overflowing timeouts are clamped rather than rejected.
+        waker.wake().unwrap();
+        assert_eq!(selector.poll(Some(Duration::from_secs(u64::MAX))).unwrap().count(), 1);
+    });
+}
+
+#[test]
+fn test_hup() {
+    each_backend(|mut selector| {
+        // `select` cannot report hangups.
+        if selector.backend() == BackendKind::Select {
+            return;
+        }
 
-    let mut pipe1 = Pipe::new().unwrap();
-    let mut pipe2 = Pipe::new().unwrap();
-    let mut selector = Selector::new().unwrap();
+        let (tx, rx) = Pipe::new().unwrap().split();
 
-    selector.register(pipe1.read, EventSet::readable()).unwrap();
-    selector.register(pipe2.read, EventSet::readable()).unwrap();
-    pipe1.write_all(b"abc").unwrap();
-    pipe2.write_all(b"def").unwrap();
+        selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+            .unwrap();
+        drop(tx);
 
-    selector.deregister(pipe1.read).unwrap();
-    assert_eq!(first_fd(&mut selector), pipe2.read);
-    selector.register(pipe1.read, EventSet::readable()).unwrap();
-    selector.deregister(pipe2.read).unwrap();
-    assert_eq!(first_fd(&mut selector), pipe1.read);
+        let fired = selector.poll(Some(Duration::from_millis(100))).unwrap().collect::<Vec<_>>();
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].evset().is_hup());
+    });
 }
@@ -0,0 +1,93 @@
+extern crate rivet;
+extern crate libc;
+
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use rivet::{Selector, EventSet, PollOpt, Token, Signals};
+
+#[test]
+fn test_signals() {
+    let mut signals = Signals::new(&[libc::SIGUSR1, libc::SIGUSR2]).unwrap();
+    let mut selector = Selector::new().unwrap();
+
+    selector.register(signals.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+        .unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+    assert!(signals.read().unwrap().is_none());
+
+    unsafe {
+        libc::raise(libc::SIGUSR1);
+        libc::raise(libc::SIGUSR2);
+    }
+
+    let fired = selector.poll(Some(Duration::from_millis(100))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(0));
+
+    let mut signos = Vec::new();
+    while let Some(info) = signals.read().unwrap() {
+        signos.push(info.signo());
+    }
+    signos.sort();
+    assert_eq!(signos, vec![libc::SIGUSR1, libc::SIGUSR2]);
+
+    assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_siginfo() {
+    let mut signals = Signals::new(&[libc::SIGHUP]).unwrap();
+
+    unsafe {
+        libc::raise(libc::SIGHUP);
+    }
+
+    let info = signals.read().unwrap().unwrap();
+    assert_eq!(info.signo(), libc::SIGHUP);
+    assert_eq!(info.pid(), unsafe { libc::getpid() });
+    assert_eq!(info.uid(), unsafe { libc::getuid() });
+}
+
+#[cfg(target_os = "linux")]
+fn is_blocked(signo: libc::c_int) -> bool {
+    unsafe {
+        let mut mask: libc::sigset_t = std::mem::zeroed();
+        libc::pthread_sigmask(libc::SIG_BLOCK, std::ptr::null(), &mut mask);
+        libc::sigismember(&mask, signo) == 1
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_drop_restores_mask() {
+    // A signal already blocked stays blocked once the `Signals` is dropped.
+    unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGUSR2);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+    }
+
+    let signals = Signals::new(&[libc::SIGUSR1, libc::SIGUSR2]).unwrap();
+    assert!(is_blocked(libc::SIGUSR1));
+    drop(signals);
+
+    assert!(!is_blocked(libc::SIGUSR1));
+    assert!(is_blocked(libc::SIGUSR2));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_drop_pending() {
+    let signals = Signals::new(&[libc::SIGTERM]).unwrap();
+
+    unsafe {
+        libc::raise(libc::SIGTERM);
+    }
+
+    // The pending signal is consumed rather than terminating the process once unblocked.
+    drop(signals);
+    assert!(!is_blocked(libc::SIGTERM));
+}
@@ -0,0 +1,102 @@
+extern crate rivet;
+
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::net::{self, Shutdown};
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token};
+use rivet::net::{TcpListener, TcpStream};
+
+fn wait(selector: &mut Selector, token: Token) -> EventSet {
+    loop {
+        let fired = selector.poll(Some(Duration::from_secs(5))).unwrap().collect::<Vec<_>>();
+        assert!(!fired.is_empty(), "timed out waiting for {:?}", token);
+
+        if let Some(fired) = fired.into_iter().find(|fired| fired.token() == token) {
+            return fired.evset();
+        }
+    }
+}
+
+#[test]
+fn test_connect_accept() {
+    let mut selector = Selector::new().unwrap();
+
+    let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    listener.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    // Nothing to accept yet.
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let mut client = TcpStream::connect(&addr).unwrap();
+    client.register(&mut selector, Token(1), EventSet::writable(), PollOpt::edge()).unwrap();
+
+    assert!(wait(&mut selector, Token(1)).is_writable());
+    assert!(client.take_error().unwrap().is_none());
+    assert_eq!(client.peer_addr().unwrap(), addr);
+
+    wait(&mut selector, Token(0));
+    let (mut server, peer) = listener.accept().unwrap();
+    assert_eq!(peer, client.local_addr().unwrap());
+    server.register(&mut selector, Token(2), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(server.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    client.write_all(b"hello").unwrap();
+    assert!(wait(&mut selector, Token(2)).is_readable());
+    assert_eq!(server.read(&mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"hello");
+
+    client.shutdown(Shutdown::Write).unwrap();
+    wait(&mut selector, Token(2));
+    assert_eq!(server.read(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_connect_refused() {
+    let mut selector = Selector::new().unwrap();
+
+    // Bind and immediately drop a listener to find a port with nothing listening.
+    let addr = net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    let stream = match TcpStream::connect(&addr) {
+        Ok(stream) => stream,
+        Err(err) => {
+            assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+            return;
+        }
+    };
+    stream.register(&mut selector, Token(0), EventSet::writable(), PollOpt::level()).unwrap();
+
+    wait(&mut selector, Token(0));
+    let err = stream.take_error().unwrap().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ConnectionRefused);
+    assert!(stream.take_error().unwrap().is_none());
+}
+
+#[test]
+fn test_nodelay() {
+    let listener = TcpListener::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+    let stream = TcpStream::connect(&listener.local_addr().unwrap()).unwrap();
+
+    stream.set_nodelay(true).unwrap();
+    assert!(stream.nodelay().unwrap());
+    stream.set_nodelay(false).unwrap();
+    assert!(!stream.nodelay().unwrap());
+}
+
+#[test]
+fn test_ipv6() {
+    let listener = match TcpListener::bind(&"[::1]:0".parse().unwrap()) {
+        Ok(listener) => listener,
+        // No IPv6 loopback in this environment.
+        Err(_) => return,
+    };
+
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.is_ipv6());
+    TcpStream::connect(&addr).unwrap();
+}
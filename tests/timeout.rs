@@ -0,0 +1,108 @@
+extern crate rivet;
+
+use std::time::{Duration, Instant};
+
+use rivet::{Selector, Token};
+
+#[test]
+fn test_set_timeout() {
+    let mut selector = Selector::new().unwrap();
+
+    let start = Instant::now();
+    selector.set_timeout(Token(0), Duration::from_millis(30));
+
+    // The pending timeout bounds an indefinite poll.
+    let fired = selector.poll(None).unwrap().collect::<Vec<_>>();
+    assert!(start.elapsed() >= Duration::from_millis(30));
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(0));
+    assert!(fired[0].is_timeout());
+    assert!(fired[0].evset().is_empty());
+
+    assert_eq!(selector.poll(Some(Duration::from_millis(50))).unwrap().count(), 0);
+}
+
+#[test]
+fn test_timeout_order() {
+    let mut selector = Selector::new().unwrap();
+
+    selector.set_timeout(Token(1), Duration::from_millis(40));
+    selector.set_timeout(Token(0), Duration::from_millis(10));
+    selector.set_timeout(Token(2), Duration::from_secs(60));
+
+    let mut tokens = Vec::new();
+    while tokens.len() < 2 {
+        let fired = selector.poll(Some(Duration::from_secs(1))).unwrap();
+        tokens.extend(fired.map(|fired| fired.token()));
+    }
+    assert_eq!(tokens, vec![Token(0), Token(1)]);
+}
+
+#[test]
+fn test_cancel_timeout() {
+    let mut selector = Selector::new().unwrap();
+
+    let timeout = selector.set_timeout(Token(0), Duration::from_millis(10));
+    assert!(selector.cancel_timeout(timeout));
+    assert!(!selector.cancel_timeout(timeout));
+
+    assert_eq!(selector.poll(Some(Duration::from_millis(50))).unwrap().count(), 0);
+
+    // A stale handle does not affect a timeout that reuses its slot.
+    let other = selector.set_timeout(Token(1), Duration::from_millis(10));
+    assert!(!selector.cancel_timeout(timeout));
+    assert!(!selector.reset_timeout(timeout, Duration::from_secs(60)));
+
+    let fired = selector.poll(Some(Duration::from_secs(1))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(1));
+    assert!(!selector.cancel_timeout(other));
+}
+
+#[test]
+fn test_reset_timeout() {
+    let mut selector = Selector::new().unwrap();
+
+    let start = Instant::now();
+    let timeout = selector.set_timeout(Token(0), Duration::from_millis(10));
+    assert!(selector.reset_timeout(timeout, Duration::from_millis(60)));
+
+    let fired = selector.poll(Some(Duration::from_secs(1))).unwrap().collect::<Vec<_>>();
+    assert!(start.elapsed() >= Duration::from_millis(60));
+    assert_eq!(fired.len(), 1);
+    assert!(fired[0].is_timeout());
+}
+
+#[test]
+fn test_timer_tick() {
+    let mut selector = Selector::new().unwrap();
+
+    assert!(selector.set_timer_tick(Duration::from_secs(0), 64).is_err());
+    selector.set_timer_tick(Duration::from_millis(10), 4).unwrap();
+
+    // Longer than a full rotation of the wheel.
+    let start = Instant::now();
+    selector.set_timeout(Token(0), Duration::from_millis(100));
+    assert!(selector.set_timer_tick(Duration::from_millis(1), 64).is_err());
+
+    // An indefinite poll waits out the earlier rotations rather than returning empty.
+    let fired = selector.poll(None).unwrap().collect::<Vec<_>>();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(0));
+}
+
+#[test]
+fn test_huge_timeout() {
+    let mut selector = Selector::new().unwrap();
+
+    selector.set_timeout(Token(0), Duration::from_secs(u64::MAX));
+    selector.set_timeout(Token(1), Duration::from_millis(10));
+
+    let fired = selector.poll(Some(Duration::from_secs(1))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(1));
+
+    // The huge timeout does not wrap around to fire early.
+    assert_eq!(selector.poll(Some(Duration::from_millis(50))).unwrap().count(), 0);
+}
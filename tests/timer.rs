@@ -0,0 +1,68 @@
+#![cfg(target_os = "linux")]
+
+extern crate rivet;
+
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::Duration;
+
+use rivet::{Selector, EventSet, PollOpt, Token, Timer};
+
+#[test]
+fn test_timeout() {
+    let mut timer = Timer::new().unwrap();
+    let mut selector = Selector::new().unwrap();
+
+    selector.register(timer.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+        .unwrap();
+    timer.set_timeout(Duration::from_millis(20)).unwrap();
+
+    let fired = selector.poll(Some(Duration::from_secs(1))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(0));
+    assert_eq!(timer.read().unwrap(), 1);
+
+    // One-shot timers do not fire again.
+    assert_eq!(selector.poll(Some(Duration::from_millis(50))).unwrap().count(), 0);
+    assert_eq!(timer.read().unwrap(), 0);
+}
+
+#[test]
+fn test_interval() {
+    let mut timer = Timer::new().unwrap();
+
+    timer.set_interval(Duration::from_millis(10)).unwrap();
+    thread::sleep(Duration::from_millis(55));
+
+    // Missed ticks are accumulated.
+    assert!(timer.read().unwrap() >= 4);
+
+    timer.cancel().unwrap();
+    thread::sleep(Duration::from_millis(20));
+    assert_eq!(timer.read().unwrap(), 0);
+}
+
+#[test]
+fn test_rearm() {
+    let mut timer = Timer::new().unwrap();
+    let mut selector = Selector::new().unwrap();
+
+    selector.register(timer.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+        .unwrap();
+
+    timer.set_timeout(Duration::from_secs(60)).unwrap();
+    timer.set_timeout(Duration::from_millis(10)).unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+    assert_eq!(timer.read().unwrap(), 1);
+}
+
+#[test]
+fn test_huge_timeout() {
+    let mut timer = Timer::new().unwrap();
+
+    // Durations beyond the range of `time_t` are clamped rather than rejected.
+    timer.set_timeout(Duration::from_secs(u64::MAX)).unwrap();
+    timer.set_interval(Duration::from_secs(u64::MAX)).unwrap();
+    thread::sleep(Duration::from_millis(10));
+    assert_eq!(timer.read().unwrap(), 0);
+}
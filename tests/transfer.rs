@@ -0,0 +1,117 @@
+#![cfg(target_os = "linux")]
+
+extern crate rivet;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::process;
+use std::time::Duration;
+use std::usize;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token, Pipe};
+use rivet::io::{Transfer, ReadExt, Status};
+use rivet::net::UnixStream;
+
+#[test]
+fn test_sendfile() {
+    let path = env::temp_dir().join(format!("rivet-{}-sendfile", process::id()));
+    let contents = (0..100_000u32).map(|i| i as u8).collect::<Vec<_>>();
+    File::create(&path).unwrap().write_all(&contents).unwrap();
+
+    let file = File::open(&path).unwrap();
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut transfer = Transfer::new().unwrap();
+
+    // Limited to `len`, and continuing from the file's offset.
+    assert_eq!(transfer.transfer(&file, &a, 10).unwrap(), (10, Status::Drained));
+
+    let mut received = vec![0; 10];
+    b.read_exact(&mut received).unwrap();
+
+    let mut buf = vec![0; 1 << 20];
+    loop {
+        match transfer.transfer(&file, &a, usize::MAX).unwrap() {
+            (_, Status::Eof) => break,
+            (_, Status::BufferFull) => {}
+            (_, Status::Drained) => unreachable!(),
+        }
+        let (n, _) = b.read_nb(&mut buf).unwrap();
+        received.extend_from_slice(&buf[..n]);
+    }
+    let (n, _) = b.read_nb(&mut buf).unwrap();
+    received.extend_from_slice(&buf[..n]);
+
+    assert_eq!(received, contents);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_splice() {
+    let mut selector = Selector::new().unwrap();
+    let (src_tx, src_rx) = Pipe::new().unwrap().split();
+    let (a, mut b) = UnixStream::pair().unwrap();
+    let mut transfer = Transfer::new().unwrap();
+
+    src_rx.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(transfer.transfer(&src_rx, &a, usize::MAX).unwrap(), (0, Status::Drained));
+
+    (&src_tx).write_all(b"forwarded").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+    assert_eq!(transfer.transfer(&src_rx, &a, usize::MAX).unwrap(), (9, Status::Drained));
+
+    let mut buf = [0; 16];
+    assert_eq!(b.read(&mut buf).unwrap(), 9);
+    assert_eq!(&buf[..9], b"forwarded");
+
+    drop(src_tx);
+    assert_eq!(transfer.transfer(&src_rx, &a, usize::MAX).unwrap(), (0, Status::Eof));
+    src_rx.deregister(&mut selector).unwrap();
+}
+
+// Reads everything queued on `stream`, returning the number of bytes read.
+fn drain(stream: &mut UnixStream) -> usize {
+    let mut buf = [0; 4096];
+    let mut total = 0;
+
+    loop {
+        match stream.read_nb(&mut buf).unwrap() {
+            (n, Status::BufferFull) => total += n,
+            (n, _) => return total + n,
+        }
+    }
+}
+
+#[test]
+fn test_splice_full() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let (c, mut d) = UnixStream::pair().unwrap();
+    let mut transfer = Transfer::new().unwrap();
+
+    let chunk = vec![7; 4096];
+    let mut sent = 0;
+
+    // Forward until `c` blocks, leaving bytes pending in the transfer's pipe.
+    loop {
+        a.write_all(&chunk).unwrap();
+        sent += chunk.len();
+
+        let (_, status) = transfer.transfer(&b, &c, usize::MAX).unwrap();
+        if status == Status::BufferFull {
+            assert!(transfer.pending() > 0);
+            break;
+        }
+    }
+
+    let mut received = 0;
+    loop {
+        received += drain(&mut d);
+        if transfer.transfer(&b, &c, usize::MAX).unwrap().1 == Status::Drained {
+            assert_eq!(transfer.pending(), 0);
+            break;
+        }
+    }
+    received += drain(&mut d);
+
+    assert_eq!(received, sent);
+}
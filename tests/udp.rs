@@ -0,0 +1,81 @@
+extern crate rivet;
+
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token};
+use rivet::net::{UdpSocket, RecvMeta};
+
+fn bind() -> UdpSocket {
+    UdpSocket::bind(&"127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+#[test]
+fn test_send_recv() {
+    let mut selector = Selector::new().unwrap();
+
+    let socket = bind();
+    let sender = bind();
+    let addr = socket.local_addr().unwrap();
+    socket.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let mut buf = [0; 64];
+    assert_eq!(socket.recv_from(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    assert_eq!(sender.send_to(b"datagram", &addr).unwrap(), 8);
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+
+    let (n, from) = socket.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"datagram");
+    assert_eq!(from, sender.local_addr().unwrap());
+
+    // Connected sockets send to and receive from their peer only.
+    sender.connect(&addr).unwrap();
+    socket.connect(&sender.local_addr().unwrap()).unwrap();
+    sender.send(b"connected").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_secs(1))).unwrap().count(), 1);
+    assert_eq!(socket.recv(&mut buf).unwrap(), 9);
+}
+
+#[test]
+fn test_mmsg() {
+    let socket = bind();
+    let sender = bind();
+    let addr = socket.local_addr().unwrap();
+
+    let payloads = (0..40u8).map(|i| vec![i; i as usize + 1]).collect::<Vec<_>>();
+    let msgs = payloads.iter()
+        .map(|payload| (&payload[..], addr))
+        .collect::<Vec<(&[u8], SocketAddr)>>();
+    assert_eq!(sender.send_mmsg(&msgs).unwrap(), 40);
+
+    let mut storage = vec![[0u8; 64]; 48];
+    let mut meta = vec![RecvMeta::default(); 48];
+    let n = {
+        let mut bufs = storage.iter_mut().map(|buf| &mut buf[..]).collect::<Vec<_>>();
+        socket.recv_mmsg(&mut bufs, &mut meta).unwrap()
+    };
+    assert_eq!(n, 40);
+
+    for (i, (buf, meta)) in storage.iter().zip(&meta).take(n).enumerate() {
+        assert_eq!(meta.size(), i + 1);
+        assert_eq!(meta.addr(), sender.local_addr().unwrap());
+        assert!(buf[..meta.size()].iter().all(|&b| b == i as u8));
+    }
+
+    let mut buf = [0u8; 8];
+    let mut bufs = [&mut buf[..]];
+    let err = socket.recv_mmsg(&mut bufs, &mut meta).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+}
+
+#[test]
+fn test_broadcast() {
+    let socket = bind();
+
+    socket.set_broadcast(true).unwrap();
+    assert!(socket.broadcast().unwrap());
+    socket.set_broadcast(false).unwrap();
+    assert!(!socket.broadcast().unwrap());
+}
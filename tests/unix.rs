@@ -0,0 +1,210 @@
+extern crate libc;
+extern crate rivet;
+
+use std::env;
+use std::fs;
+use std::io::prelude::*;
+use std::io::ErrorKind;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+use rivet::{Selector, Evented, EventSet, PollOpt, Token};
+use rivet::net::{UnixAddr, UnixStream, UnixListener, UnixDatagram, UnixSeqpacket,
+                 UnixSeqpacketListener};
+
+// Returns a socket path unique to this process and test, removing any stale socket.
+fn socket_path(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rivet-{}-{}.sock", process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn wait(selector: &mut Selector) -> usize {
+    selector.poll(Some(Duration::from_secs(1))).unwrap().count()
+}
+
+#[test]
+fn test_stream_pair() {
+    let mut selector = Selector::new().unwrap();
+    let (mut a, mut b) = UnixStream::pair().unwrap();
+
+    a.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(a.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    b.write_all(b"pair").unwrap();
+    assert_eq!(wait(&mut selector), 1);
+    assert_eq!(a.read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf[..4], b"pair");
+
+    assert!(a.local_addr().unwrap().is_unnamed());
+    assert!(a.peer_addr().unwrap().is_unnamed());
+}
+
+#[test]
+fn test_listener_path() {
+    let mut selector = Selector::new().unwrap();
+    let path = socket_path("listener");
+
+    let listener = UnixListener::bind(&path).unwrap();
+    assert_eq!(listener.local_addr().unwrap().as_path(), Some(path.as_path()));
+    listener.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let mut client = UnixStream::connect(&path).unwrap();
+    assert_eq!(wait(&mut selector), 1);
+
+    let (mut server, addr) = listener.accept().unwrap();
+    assert!(addr.is_unnamed());
+    assert_eq!(client.peer_addr().unwrap().as_path(), Some(path.as_path()));
+
+    client.write_all(b"hello").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(server.read(&mut buf).unwrap(), 5);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_listener_abstract() {
+    let name = format!("rivet-{}-abstract", process::id());
+    let addr = UnixAddr::abstract_name(&name);
+
+    let listener = UnixListener::bind_addr(&addr).unwrap();
+    assert_eq!(listener.local_addr().unwrap(), addr);
+    assert_eq!(listener.local_addr().unwrap().as_abstract_name(), Some(name.as_bytes()));
+
+    let client = UnixStream::connect_addr(&addr).unwrap();
+    assert_eq!(client.peer_addr().unwrap(), addr);
+    listener.accept().unwrap();
+}
+
+#[test]
+fn test_path_too_long() {
+    let path = "x".repeat(1024);
+    let err = UnixListener::bind(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_datagram() {
+    let mut selector = Selector::new().unwrap();
+    let path = socket_path("datagram");
+
+    let socket = UnixDatagram::bind(&path).unwrap();
+    socket.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let sender = UnixDatagram::unbound().unwrap();
+    assert_eq!(sender.send_to(b"first", &path).unwrap(), 5);
+    assert_eq!(sender.send_to(b"second", &path).unwrap(), 6);
+    assert_eq!(wait(&mut selector), 1);
+
+    // Message boundaries are preserved.
+    let mut buf = [0; 16];
+    let (n, from) = socket.recv_from(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"first");
+    assert!(from.is_unnamed());
+    assert_eq!(socket.recv(&mut buf).unwrap(), 6);
+    assert_eq!(socket.recv(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+    let (a, b) = UnixDatagram::pair().unwrap();
+    a.send(b"paired").unwrap();
+    assert_eq!(b.recv(&mut buf).unwrap(), 6);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_seqpacket() {
+    let mut selector = Selector::new().unwrap();
+    let path = socket_path("seqpacket");
+
+    let listener = UnixSeqpacketListener::bind(&path).unwrap();
+    listener.register(&mut selector, Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+
+    let client = UnixSeqpacket::connect(&path).unwrap();
+    assert_eq!(wait(&mut selector), 1);
+    let (server, _) = listener.accept().unwrap();
+
+    client.send(b"one").unwrap();
+    client.send(b"two!").unwrap();
+
+    let mut buf = [0; 16];
+    assert_eq!(server.recv(&mut buf).unwrap(), 3);
+    assert_eq!(server.recv(&mut buf).unwrap(), 4);
+    assert_eq!(&buf[..4], b"two!");
+
+    let (a, b) = UnixSeqpacket::pair().unwrap();
+    a.send(b"packet").unwrap();
+    assert_eq!(b.recv(&mut buf).unwrap(), 6);
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_fd_passing() {
+    let (mut a, b) = UnixStream::pair().unwrap();
+    let (mut near, far) = ::std::os::unix::net::UnixStream::pair().unwrap();
+
+    assert_eq!(a.send_with_fds(b"fd", &[far.as_raw_fd()]).unwrap(), 2);
+    drop(far);
+
+    let mut buf = [0; 16];
+    let mut fds = [-1; 4];
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).unwrap(), (2, 1));
+    assert_eq!(&buf[..2], b"fd");
+
+    // The received descriptor is nonblocking and close-on-exec.
+    unsafe {
+        assert!(libc::fcntl(fds[0], libc::F_GETFL) & libc::O_NONBLOCK != 0);
+        assert!(libc::fcntl(fds[0], libc::F_GETFD) & libc::FD_CLOEXEC != 0);
+    }
+
+    let mut received = unsafe { UnixStream::from_raw_fd(fds[0]) };
+    assert_eq!(received.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+    near.write_all(b"passed").unwrap();
+    assert_eq!(received.read(&mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"passed");
+
+    // Plain data carries no descriptors.
+    a.write_all(b"plain").unwrap();
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).unwrap(), (5, 0));
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).unwrap_err().kind(), ErrorKind::WouldBlock);
+}
+
+#[test]
+fn test_fd_passing_seqpacket() {
+    let (a, b) = UnixSeqpacket::pair().unwrap();
+    let (x, y) = UnixDatagram::pair().unwrap();
+
+    a.send_with_fds(b"two", &[x.as_raw_fd(), y.as_raw_fd()]).unwrap();
+
+    let mut buf = [0; 16];
+    let mut fds = [-1; 2];
+    assert_eq!(b.recv_with_fds(&mut buf, &mut fds).unwrap(), (3, 2));
+
+    let (x, y) = unsafe { (UnixDatagram::from_raw_fd(fds[0]), UnixDatagram::from_raw_fd(fds[1])) };
+    x.send(b"ping").unwrap();
+    assert_eq!(y.recv(&mut buf).unwrap(), 4);
+}
+
+#[test]
+fn test_peer_cred() {
+    let (a, _b) = UnixStream::pair().unwrap();
+    let cred = a.peer_cred().unwrap();
+
+    unsafe {
+        assert_eq!(cred.uid(), libc::geteuid());
+        assert_eq!(cred.gid(), libc::getegid());
+        if cfg!(target_os = "linux") {
+            assert_eq!(cred.pid(), Some(libc::getpid()));
+        }
+    }
+
+    let (a, _b) = UnixSeqpacket::pair().unwrap();
+    assert_eq!(a.peer_cred().unwrap().uid(), unsafe { libc::geteuid() });
+}
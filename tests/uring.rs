@@ -0,0 +1,173 @@
+#![cfg(all(target_os = "linux", feature = "io_uring"))]
+
+extern crate rivet;
+extern crate libc;
+
+use std::io::prelude::*;
+use std::net;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+use rivet::{Selector, BackendKind, EventSet, PollOpt, Token, Pipe};
+use rivet::uring::{Uring, Completion};
+
+// Returns a `Uring`, or `None` if the kernel does not support or permit one.
+fn uring() -> Option<Uring> {
+    match Uring::new() {
+        Ok(uring) => Some(uring),
+        Err(err) => {
+            println!("skipping, io_uring unavailable: {}", err);
+            None
+        }
+    }
+}
+
+fn complete_one(uring: &mut Uring) -> Completion {
+    let mut completions = Vec::new();
+    assert_eq!(uring.complete(&mut completions, Some(Duration::from_secs(5))).unwrap(), 1);
+    completions.pop().unwrap()
+}
+
+#[test]
+fn test_fallback() {
+    let kind = BackendKind::default();
+    let selector = Selector::new().unwrap();
+
+    // Only an `io_uring` default falls back, and only when a ring cannot be set up.
+    if kind == BackendKind::Uring && Selector::with_backend(BackendKind::Uring).is_err() {
+        assert_eq!(selector.backend(), BackendKind::Epoll);
+    } else {
+        assert_eq!(selector.backend(), kind);
+    }
+}
+
+#[test]
+fn test_drop_then_poll() {
+    let mut selector = match Selector::with_backend(BackendKind::Uring) {
+        Ok(selector) => selector,
+        Err(_) => return,
+    };
+    let (_tx, rx) = Pipe::new().unwrap().split();
+
+    selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_millis(10))).unwrap().count(), 0);
+    selector.deregister(rx.as_raw_fd()).unwrap();
+    drop(selector);
+
+    // Tearing down the ring does not cut short a later blocking poll on this thread.
+    let mut selector = Selector::with_backend(BackendKind::Epoll).unwrap();
+    let start = Instant::now();
+    assert_eq!(selector.poll(Some(Duration::from_millis(300))).unwrap().count(), 0);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn test_reregister() {
+    let mut selector = match Selector::with_backend(BackendKind::Uring) {
+        Ok(selector) => selector,
+        Err(_) => return,
+    };
+    let (mut tx, rx) = Pipe::new().unwrap().split();
+
+    selector.register(rx.as_raw_fd(), Token(0), EventSet::writable(), PollOpt::level()).unwrap();
+    assert!(selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level())
+        .is_err());
+    tx.write_all(b"ready").unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+
+    selector.reregister(rx.as_raw_fd(), Token(1), EventSet::readable(), PollOpt::level()).unwrap();
+    let fired = selector.poll(Some(Duration::from_millis(100))).unwrap().collect::<Vec<_>>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].token(), Token(1));
+    assert!(fired[0].evset().is_readable());
+
+    selector.deregister(rx.as_raw_fd()).unwrap();
+    assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+}
+
+#[test]
+fn test_deregister_armed() {
+    let mut selector = match Selector::with_backend(BackendKind::Uring) {
+        Ok(selector) => selector,
+        Err(_) => return,
+    };
+    let (_tx, rx) = Pipe::new().unwrap().split();
+
+    selector.register(rx.as_raw_fd(), Token(0), EventSet::readable(), PollOpt::level()).unwrap();
+    selector.deregister(rx.as_raw_fd()).unwrap();
+
+    // The completions of the removal and the poll it cancels do not end the wait early.
+    let start = Instant::now();
+    assert_eq!(selector.poll(Some(Duration::from_millis(100))).unwrap().count(), 0);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[test]
+fn test_read_write() {
+    let mut uring = match uring() {
+        Some(uring) => uring,
+        None => return,
+    };
+    let (tx, rx) = Pipe::new().unwrap().split();
+
+    uring.read(rx.as_raw_fd(), 64, Token(0)).unwrap();
+    uring.write(tx.as_raw_fd(), b"hello world".to_vec(), Token(1)).unwrap();
+    assert_eq!(uring.in_flight(), 2);
+
+    let mut completions = Vec::new();
+    while completions.len() < 2 {
+        uring.complete(&mut completions, Some(Duration::from_secs(5))).unwrap();
+    }
+    assert_eq!(uring.in_flight(), 0);
+
+    for completion in completions {
+        match completion {
+            Completion::Read(token, res) => {
+                assert_eq!(token, Token(0));
+                assert_eq!(res.unwrap(), b"hello world");
+            }
+            Completion::Write(token, res, buf) => {
+                assert_eq!(token, Token(1));
+                assert_eq!(res.unwrap(), 11);
+                assert_eq!(buf, b"hello world");
+            }
+            completion => panic!("unexpected completion: {:?}", completion),
+        }
+    }
+
+    // The write end is closed, so the next read is end-of-file.
+    drop(tx);
+    uring.read(rx.as_raw_fd(), 16, Token(2)).unwrap();
+    match complete_one(&mut uring) {
+        Completion::Read(_, res) => assert!(res.unwrap().is_empty()),
+        completion => panic!("unexpected completion: {:?}", completion),
+    }
+
+    // With nothing in flight, completing returns at once.
+    let mut completions = Vec::new();
+    assert_eq!(uring.complete(&mut completions, None).unwrap(), 0);
+}
+
+#[test]
+fn test_accept() {
+    let mut uring = match uring() {
+        Some(uring) => uring,
+        None => return,
+    };
+    let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+    uring.accept(listener.as_raw_fd(), Token(7)).unwrap();
+    let _client = net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    match complete_one(&mut uring) {
+        Completion::Accept(token, res) => {
+            assert_eq!(token, Token(7));
+            let fd = res.unwrap();
+            unsafe {
+                assert!(libc::fcntl(fd, libc::F_GETFL) & libc::O_NONBLOCK != 0);
+                libc::close(fd);
+            }
+        }
+        completion => panic!("unexpected completion: {:?}", completion),
+    }
+}